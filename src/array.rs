@@ -0,0 +1,117 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem::size_of;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+/// Element that can be stored in a binary file as fixed-width little endian.
+pub trait Element: Copy {
+    /// Width of the element in the file, in bytes.
+    const WIDTH: usize;
+
+    fn from_le(bytes: &[u8]) -> Self;
+    fn write_le<W: Write>(self, output: &mut W) -> io::Result<()>;
+}
+
+impl Element for u8 {
+    const WIDTH: usize = 1;
+    fn from_le(bytes: &[u8]) -> Self { bytes[0] }
+    fn write_le<W: Write>(self, output: &mut W) -> io::Result<()> { output.write_all(&[self]) }
+}
+
+impl Element for u32 {
+    const WIDTH: usize = 4;
+    fn from_le(bytes: &[u8]) -> Self { u32::from_le_bytes(bytes.try_into().unwrap()) }
+    fn write_le<W: Write>(self, output: &mut W) -> io::Result<()> { output.write_all(&self.to_le_bytes()) }
+}
+
+impl Element for u64 {
+    const WIDTH: usize = 8;
+    fn from_le(bytes: &[u8]) -> Self { u64::from_le_bytes(bytes.try_into().unwrap()) }
+    fn write_le<W: Write>(self, output: &mut W) -> io::Result<()> { output.write_all(&self.to_le_bytes()) }
+}
+
+impl Element for usize {
+    const WIDTH: usize = 8;
+    fn from_le(bytes: &[u8]) -> Self { u64::from_le_bytes(bytes.try_into().unwrap()) as usize }
+    fn write_le<W: Write>(self, output: &mut W) -> io::Result<()> {
+        output.write_all(&(self as u64).to_le_bytes())
+    }
+}
+
+/// Read-only array either owning its elements or viewing a region of a memory-mapped file.
+pub enum Array<T: Element> {
+    Owned(Vec<T>),
+    Mapped { mmap: Arc<Mmap>, offset: usize, len: usize },
+}
+
+impl<T: Element> Array<T> {
+    /// Views `len` elements starting at byte `offset` of the mapped file.
+    /// Falls back to decoding a copy if the in-memory layout differs from the file layout.
+    pub fn mapped(mmap: &Arc<Mmap>, offset: usize, len: usize) -> Self {
+        let end = offset + len * T::WIDTH;
+        assert!(end <= mmap.len(), "Mapped array is out of file bounds.");
+
+        let native = cfg!(target_endian = "little") && size_of::<T>() == T::WIDTH;
+        let aligned = mmap[offset..].as_ptr().align_offset(std::mem::align_of::<T>()) == 0;
+        if native && aligned {
+            Array::Mapped { mmap: Arc::clone(mmap), offset, len }
+        } else {
+            Array::Owned(mmap[offset..end].chunks_exact(T::WIDTH).map(T::from_le).collect())
+        }
+    }
+
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        for &x in self.iter() { x.write_le(output)?; }
+        Ok(())
+    }
+}
+
+impl<T: Element> Deref for Array<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Array::Owned(v) => v,
+            Array::Mapped { mmap, offset, len } => {
+                // safe because `mapped` checked bounds, alignment and layout of T
+                unsafe { std::slice::from_raw_parts(mmap.as_ptr().add(*offset) as *const T, *len) }
+            }
+        }
+    }
+}
+
+impl<T: Element> From<Vec<T>> for Array<T> {
+    fn from(v: Vec<T>) -> Self { Array::Owned(v) }
+}
+
+/// Maps the whole file into memory.
+pub fn map_file(file: &File) -> io::Result<Arc<Mmap>> {
+    // safe as long as the file is not modified while maria is running
+    let mmap = unsafe { Mmap::map(file)? };
+    Ok(Arc::new(mmap))
+}
+
+/// Reads the `i`-th little endian u64 from the buffer.
+pub fn read_u64(buffer: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(buffer[8*i..8*i+8].try_into().unwrap())
+}
+
+/// Number of bytes needed to pad `len` to a multiple of 8.
+pub fn padding(len: usize) -> usize { (8 - len % 8) % 8 }
+
+#[test]
+fn mapped_array_reads_little_endian_values() {
+    let dir = crate::tests::TempDir::new("mapped_array");
+    let filename = dir.join("array.bin");
+    {
+        let mut file = File::create(&filename).unwrap();
+        let values: Array<usize> = vec![1, 2, 1 << 40].into();
+        values.write(&mut file).unwrap();
+    }
+    let mmap = map_file(&File::open(&filename).unwrap()).unwrap();
+    let array: Array<usize> = Array::mapped(&mmap, 8, 2);
+    assert_eq!(&array[..], &[2, 1 << 40]);
+}
@@ -0,0 +1,63 @@
+/// Packs unsigned integers of arbitrary widths (at most 64 bits) into u64 words.
+#[derive(Default)]
+pub struct BitWriter {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitWriter {
+    pub fn push(&mut self, value: u64, width: usize) {
+        debug_assert!(width == 64 || value >> width == 0, "Value {value} does not fit into {width} bits.");
+        if width == 0 { return; }
+        let offset = self.len % 64;
+        if offset == 0 { self.words.push(0); }
+        *self.words.last_mut().unwrap() |= value << offset;
+        if offset + width > 64 { self.words.push(value >> (64 - offset)); }
+        self.len += width;
+    }
+
+    /// Removes and returns the words that are completely filled.
+    pub fn take_full_words(&mut self) -> Vec<u64> {
+        let full = self.len / 64;
+        self.len -= 64 * full;
+        return self.words.drain(..full).collect();
+    }
+
+    pub fn into_words(self) -> Vec<u64> { self.words }
+}
+
+/// Reads integers packed by `BitWriter` sequentially.
+pub struct BitReader<'a> {
+    words: &'a [u64],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(words: &'a [u64]) -> Self { BitReader { words, pos: 0 } }
+
+    pub fn read(&mut self, width: usize) -> u64 {
+        if width == 0 { return 0; }
+        let (word, offset) = (self.pos / 64, self.pos % 64);
+        let mut value = self.words[word] >> offset;
+        if offset + width > 64 { value |= self.words[word + 1] << (64 - offset); }
+        self.pos += width;
+        return if width == 64 { value } else { value & ((1 << width) - 1) };
+    }
+}
+
+/// Number of bits needed to store `x`.
+pub fn width(x: u64) -> usize { (u64::BITS - x.leading_zeros()) as usize }
+
+#[test]
+fn packed_values_are_read_back() {
+    let values = [(5, 3), (0, 0), (1 << 40, 41), (u64::MAX, 64), (1, 1), (12345, 17)];
+    let mut writer = BitWriter::default();
+    let mut words = Vec::new();
+    for (value, width) in values {
+        writer.push(value, width);
+        words.extend(writer.take_full_words());
+    }
+    words.extend(writer.into_words());
+    let mut reader = BitReader::new(&words);
+    for (value, width) in values { assert_eq!(reader.read(width), value); }
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use crate::checksum::file_checksum;
+use crate::gp::GraphPos;
+use crate::grammar::Grammar;
+use crate::index::Index;
+use crate::layout::Layout;
+use crate::pred::Predecessor;
+use crate::tag::{check_header, read_tag_array};
+use crate::graph::Graph;
+use crate::{lce, path_coordinates};
+
+/// Number of characters printed around a discrepancy.
+const CONTEXT: usize = 10;
+
+/// Checks that the index is complete and all its components were built from the graph.
+pub fn check_index(gfa: &Path, dir: &Path) -> Result<(), String> {
+    println!("Checking index {}", dir.display());
+    let index = Index::open(dir)?;
+    index.verify()?;
+    if index.graph_checksum != file_checksum(gfa) {
+        return Err(format!("Index {} was built from a different graph than {}.", dir.display(), gfa.display()));
+    }
+    let layout = Layout::read(&index.layout());
+    let mut graph = Graph::from_file(gfa);
+    if layout.reverse_paths { graph = graph.with_reverse_paths(); }
+    if layout != Layout::new(&graph) {
+        return Err(format!("Layout {} does not match the paths of the graph.", index.layout().display()));
+    }
+    return check(&graph, index.graph_checksum, &index.tag(), &index.grammar());
+}
+
+/// Checks that the grammar and the tag array were built from the graph, whose GFA file has the checksum.
+/// Returns a description of the first discrepancy found.
+pub fn check(graph: &Graph, graph_checksum: u64, tag: &Path, slp: &Path) -> Result<(), String> {
+    let (path_starts, path_names, node_starts, node_names) = path_coordinates(graph);
+    let node_overlaps = graph.overlaps();
+    let text = graph.text();
+    let grammar = Grammar::from_file(slp);
+    let locate = |pos: usize| {
+        let p = path_starts.argpred(pos);
+        let i = node_starts.argpred(pos);
+        format!("path {} position {}, node {} offset {}",
+            path_names[p], pos - path_starts[p], node_names[i].to_path(&graph.segment_names), pos - node_starts[i] + node_overlaps[i])
+    };
+
+    println!("Checking grammar {}", slp.display());
+    let n = *node_starts.last().unwrap();
+    if text.len() != n {
+        return Err(format!("Path sequences have length {}, but the nodes on paths have total length {n}.", text.len()));
+    }
+    if grammar.len() != n {
+        return Err(format!("Grammar generates a text of length {}, but the paths have total length {n}.", grammar.len()));
+    }
+    if let Some(pos) = grammar.iter_from(0).zip(&text).position(|(c, &t)| c != t) {
+        let start = pos.saturating_sub(CONTEXT);
+        return Err(format!(
+            "Grammar differs from the graph at text position {pos} ({}):\n\tgraph:   {}\n\tgrammar: {}",
+            locate(pos),
+            String::from_utf8_lossy(&text[start..n.min(pos + CONTEXT)]),
+            String::from_utf8_lossy(&grammar.extract(start, pos + CONTEXT - start)),
+        ));
+    }
+
+    println!("Checking tag array {}", tag.display());
+    check_header(tag, graph_checksum, n)?;
+    let (ssa, stag, run_lengths) = read_tag_array(tag);
+    if ssa.len() % 2 != 0 {
+        return Err(format!("Tag array has {} samples, but every run needs to be sampled at its start and end.", ssa.len()));
+    }
+    if let Some(run_lengths) = run_lengths {
+        if let Some(k) = (0..run_lengths.len()).find(|&k| run_lengths[k] == 0) {
+            return Err(format!("Tag array run {k} is empty."));
+        }
+        if run_lengths.iter().sum::<usize>() != n {
+            return Err(format!("Tag array runs have total length {}, but the text has length {n}.",
+                run_lengths.iter().sum::<usize>()));
+        }
+    }
+    for k in 0..ssa.len() {
+        let sa = ssa[k];
+        if sa >= n {
+            return Err(format!("Tag array entry {k}: suffix {sa} is out of the text of length {n}."));
+        }
+        let i = node_starts.argpred(sa);
+        let expected = GraphPos{pos: sa - node_starts[i] + node_overlaps[i], ..node_names[i]};
+        if stag[k] != expected {
+            return Err(format!("Tag array entry {k}: suffix {sa} ({}) should have tag {expected}, but has tag {}.",
+                locate(sa), stag[k]));
+        }
+        if k % 2 == 1 && stag[k-1] != stag[k] {
+            return Err(format!("Tag array entries {} and {k}: run starts with tag {} and ends with tag {}.",
+                k-1, stag[k-1], stag[k]));
+        }
+        if k % 2 == 0 && k > 0 && stag[k-1] == stag[k] {
+            return Err(format!("Tag array entries {} and {k}: consecutive runs have the same tag {}.", k-1, stag[k]));
+        }
+        if k > 0 && ssa[k-1] != ssa[k] {
+            let (l, smaller) = lce(&grammar, ssa[k-1], sa);
+            if !smaller {
+                return Err(format!(
+                    "Tag array entries {} and {k}: suffixes are not sorted, the common prefix has length {l}:\n\t{}: {}\n\t{}: {}",
+                    k-1, ssa[k-1], String::from_utf8_lossy(&grammar.extract(ssa[k-1], l + 1)),
+                    sa, String::from_utf8_lossy(&grammar.extract(sa, l + 1)),
+                ));
+            }
+        }
+    }
+    return Ok(());
+}
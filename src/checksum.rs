@@ -0,0 +1,33 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn update(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    return hash;
+}
+
+/// Returns the 64-bit FNV-1a hash of the bytes.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    return update(OFFSET_BASIS, bytes);
+}
+
+/// Returns the 64-bit FNV-1a hash of the file content.
+pub fn file_checksum<P: AsRef<Path> + ?Sized>(filename: &P) -> u64 {
+    let file = File::open(filename).expect("Cannot open file for checksum.");
+    let mut reader = BufReader::new(file);
+    let mut buffer = [0; 1 << 16];
+    let mut hash = OFFSET_BASIS;
+    loop {
+        let read = reader.read(&mut buffer).expect("Error reading file for checksum.");
+        if read == 0 { break; }
+        hash = update(hash, &buffer[..read]);
+    }
+    return hash;
+}
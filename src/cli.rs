@@ -8,14 +8,23 @@ fn print_maria_noargs_or_help() {
     assert_eq!(args1.to_string(), args2.to_string());
     assert_eq!(args1.to_string(), "\
         Usage: \n\
-        maria index <graph>.gfa -t <triggers.txt>\n\
-        maria align <graph>.gfa <reads>.fastq > <output.gaf> \n\
+        maria index <graph>.gfa (-t <triggers.txt> | --auto-triggers)\n\
+        maria align <graph>.maria <reads>.fastq > <output.gaf> \n\
+        maria grammar <graph>.gfa\n\
+        maria convert <grammar> <graph>.slp\n\
+        maria check <graph>.gfa\n\
+        maria stats --grammar <graph>.slp\n\
+        maria stats --tag <graph>.maria/graph.tag\n\
         \n\
         \n\
         Commands:\n  \
-          index  Create a run-length compressed tag array <graph>.tag\n  \
-          align  Find all positions of a match in a graph. Matches to the reference can be found by MONI\n  \
-          help   Print this message or the help of the given subcommand(s)\n\
+          index    Create an index <graph>.maria with the grammar, tag array and layout of the graph\n  \
+          align    Find all positions of a match in a graph. Matches to the reference can be found by MONI\n  \
+          grammar  Create an SLP grammar <graph>.slp of the concatenated path sequences\n  \
+          convert  Convert a plain SLP grammar to the binary format, which is memory-mapped by align\n  \
+          check    Check that the index <graph>.maria was built from the graph\n  \
+          stats    Print statistics of the index\n  \
+          help     Print this message or the help of the given subcommand(s)\n\
         \n\
         Options:\n  \
           -h, --help  Print help\n\
@@ -29,19 +38,32 @@ fn print_maria_index_noargs_or_help() {
     let args2 = Args::try_parse_from(["maria", "index", "-h"].iter()).err().unwrap();
     assert_eq!(args1.to_string(), args2.to_string());
     assert_eq!(args1.to_string(), "\
-        Create a run-length compressed tag array <graph>.tag\n\
+        Create an index <graph>.maria with the grammar, tag array and layout of the graph\n\
         \n\
-        Usage: maria index <GFA> -t <TRIGGERS>\n\
+        Usage: maria index <GFA> (-t <TRIGGERS> | --auto-triggers) [OPTIONS]\n\
         \n\
         Arguments:\n  \
           <GFA>  Graph in GFA format\n\
         \n\
         Options:\n  \
-          -t <TRIGGERS>      Trigger file used for prefix-free tag array construction.\n                     \
-                             Contains one trigger (e.g. TAA) per line.\n                     \
-                             Triggers do not influence the resulting tag array,\n                     \
-                             only the time and space complexity of the construction.\n  \
-          -h, --help         Print help\n\
+          -t <TRIGGERS>                  Trigger file used for prefix-free tag array construction.\n                                 \
+                                         Contains one trigger (e.g. TAA) per line.\n                                 \
+                                         Triggers do not influence the resulting tag array,\n                                 \
+                                         only the time and space complexity of the construction.\n      \
+              --auto-triggers            Select triggers from the path sequences instead of a trigger file\n      \
+              --phrase-len <PHRASE_LEN>  Average length of phrases of the selected triggers [default: 64]\n      \
+              --text                     Write the tag array as text lines <sa>\\t<id><sign>:<pos> instead of the binary format\n  \
+          -f, --force                    Rebuild all components, even if they are up to date\n      \
+              --grammar <GRAMMAR>        Grammar of the path sequences used instead of building one, for large graphs:\n                                 \
+                                         a grammar written by maria grammar or maria convert,\n                                 \
+                                         or the prefix of bigrepair output <GRAMMAR>.R and <GRAMMAR>.C\n      \
+              --moni <MONI>              MONI executable, builds the MONI index <graph>.maria/moni of the path sequences\n      \
+              --naive                    Build the tag array from a plain suffix array instead of the prefix-free parse.\n                                 \
+                                         Needs memory proportional to the length of the paths, meant for validation.\n      \
+              --threads <THREADS>        Number of threads sampling the tag array [default: 1]\n      \
+              --revcomp                  Index the reverse complement of every path as well.\n                                 \
+                                         Matches of reverse complemented paths are reported on the forward paths with strand -.\n  \
+          -h, --help                     Print help\n\
     ");
 }
 
@@ -54,22 +76,115 @@ fn print_maria_align_noargs_or_help() {
     assert_eq!(args1.to_string(), "\
         Find all positions of a match in a graph. Matches to the reference can be found by MONI\n\
         \n\
-        Usage: maria align <GFA> <READS> > output.gaf\n\
+        Usage: maria align <INDEX> <READS> > output.gaf\n\
         \n\
         Arguments:\n  \
-          <GFA>    Graph in GFA format. For <graph>.gfa, tag array <graph>.tag and SLP grammar <graph>.slp need to be present\n  \
-          <READS>  File containing reads. For <reads>.fastq, MONI outputs <reads>.mems and <reads>.pointers need to be present\n\
+          <INDEX>  Index created by maria index, e.g. <graph>.maria. The graph itself is not needed\n  \
+          <READS>  File containing reads. For <reads>.fastq, MONI outputs <reads>.mems and <reads>.pointers need to be present. Read lengths are taken from <reads>.fastq or, if it does not exist, from <reads>.lengths of MONI ms\n\
         \n\
         Options:\n  \
-          -o <OUTPUT>      Output in GAF format [default: stdout]\n  \
+          -o <OUTPUT>      Output in GAF format [default: stdout]\n      \
+              --paths      Annotate every record with the number (np:i) and names (pn:Z) of the paths containing its node walk\n  \
           -h, --help       Print help\n\
     ");
 }
 
+#[rustfmt::skip]
+#[test]
+fn print_maria_grammar_noargs_or_help() {
+    let args1 = Args::try_parse_from(["maria", "grammar"].iter()).err().unwrap();
+    let args2 = Args::try_parse_from(["maria", "grammar", "-h"].iter()).err().unwrap();
+    assert_eq!(args1.to_string(), args2.to_string());
+    assert_eq!(args1.to_string(), "\
+        Create an SLP grammar <graph>.slp of the concatenated path sequences\n\
+        \n\
+        Usage: maria grammar <GFA> [-b]\n\
+        \n\
+        Arguments:\n  \
+          <GFA>  Graph in GFA format\n\
+        \n\
+        Options:\n  \
+          -b, --balance  Balance the grammar, so that random access takes O(log n) time\n  \
+          -h, --help     Print help\n\
+    ");
+}
+
+#[rustfmt::skip]
+#[test]
+fn print_maria_convert_noargs_or_help() {
+    let args1 = Args::try_parse_from(["maria", "convert"].iter()).err().unwrap();
+    let args2 = Args::try_parse_from(["maria", "convert", "-h"].iter()).err().unwrap();
+    assert_eq!(args1.to_string(), args2.to_string());
+    assert_eq!(args1.to_string(), "\
+        Convert a plain SLP grammar to the binary format, which is memory-mapped by align\n\
+        \n\
+        Usage: maria convert <GRAMMAR> <OUTPUT> [-b]\n\
+        \n\
+        Arguments:\n  \
+          <GRAMMAR>  Prefix of bigrepair output <GRAMMAR>.R and <GRAMMAR>.C,\n             \
+                     or a plain grammar with one rule `X Y` per line\n  \
+          <OUTPUT>   Binary grammar, usually <graph>.slp\n\
+        \n\
+        Options:\n  \
+          -b, --balance  Balance the grammar, so that random access takes O(log n) time\n  \
+          -h, --help     Print help\n\
+    ");
+}
+
+#[rustfmt::skip]
+#[test]
+fn print_maria_check_noargs_or_help() {
+    let args1 = Args::try_parse_from(["maria", "check"].iter()).err().unwrap();
+    let args2 = Args::try_parse_from(["maria", "check", "-h"].iter()).err().unwrap();
+    assert_eq!(args1.to_string(), args2.to_string());
+    assert_eq!(args1.to_string(), "\
+        Check that the index <graph>.maria was built from the graph\n\
+        \n\
+        Usage: maria check <GFA>\n\
+        \n\
+        Arguments:\n  \
+          <GFA>  Graph in GFA format. Index <graph>.maria needs to be present\n\
+        \n\
+        Options:\n  \
+          -h, --help  Print help\n\
+    ");
+}
+
+#[rustfmt::skip]
+#[test]
+fn print_maria_stats_noargs_or_help() {
+    let args1 = Args::try_parse_from(["maria", "stats"].iter()).err().unwrap();
+    let args2 = Args::try_parse_from(["maria", "stats", "-h"].iter()).err().unwrap();
+    assert_eq!(args1.to_string(), args2.to_string());
+    assert_eq!(args1.to_string(), "\
+        Print statistics of the index\n\
+        \n\
+        Usage: maria stats [--grammar <GRAMMAR> [--gfa <GFA>]] [--tag <TAG>]\n\
+        \n\
+        Options:\n      \
+              --grammar <GRAMMAR>  SLP grammar, e.g. <graph>.slp. Reports its size, height and compression\n      \
+              --gfa <GFA>          Graph in GFA format, the grammar is compared to its sequences\n      \
+              --tag <TAG>          Tag array, e.g. <graph>.maria/graph.tag. Reports its runs and the nodes with most runs\n  \
+          -h, --help               Print help\n\
+    ");
+}
+
+#[test]
+fn maria_stats_gfa_requires_grammar() {
+    let error = Args::try_parse_from(["maria", "stats", "--gfa", "graph.gfa"].iter()).err().unwrap();
+    assert_eq!(error.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    assert!(Args::try_parse_from(["maria", "stats", "--grammar", "graph.slp", "--gfa", "graph.gfa"].iter()).is_ok());
+}
+
 #[derive(Parser, Debug)]
 #[command(override_usage = "\n\
-    maria index <graph>.gfa -t <triggers.txt>\n\
-    maria align <graph>.gfa <reads>.fastq > <output.gaf> \n\
+    maria index <graph>.gfa (-t <triggers.txt> | --auto-triggers)\n\
+    maria align <graph>.maria <reads>.fastq > <output.gaf> \n\
+    maria grammar <graph>.gfa\n\
+    maria convert <grammar> <graph>.slp\n\
+    maria check <graph>.gfa\n\
+    maria stats --grammar <graph>.slp\n\
+    maria stats --tag <graph>.maria/graph.tag\n\
 ")]
 pub struct Args {
     #[command(subcommand)]
@@ -78,8 +193,8 @@ pub struct Args {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Create a run-length compressed tag array <graph>.tag
-    #[command(override_usage = "maria index <GFA> -t <TRIGGERS>", arg_required_else_help = true)]
+    /// Create an index <graph>.maria with the grammar, tag array and layout of the graph
+    #[command(override_usage = "maria index <GFA> (-t <TRIGGERS> | --auto-triggers) [OPTIONS]", arg_required_else_help = true)]
     Index {
         /// Graph in GFA format
         gfa: String,
@@ -88,24 +203,121 @@ pub enum Commands {
         /// Contains one trigger (e.g. TAA) per line.
         /// Triggers do not influence the resulting tag array,
         /// only the time and space complexity of the construction.
-        #[arg(short = 't', verbatim_doc_comment)]
-        triggers: String,
+        #[arg(short = 't', verbatim_doc_comment, required_unless_present = "auto_triggers")]
+        triggers: Option<String>,
+
+        /// Select triggers from the path sequences instead of a trigger file
+        #[arg(long = "auto-triggers", conflicts_with = "triggers")]
+        auto_triggers: bool,
+
+        /// Average length of phrases of the selected triggers
+        #[arg(long = "phrase-len", default_value_t = 64)]
+        phrase_len: usize,
+
+        /// Write the tag array as text lines <sa>\t<id><sign>:<pos> instead of the binary format
+        #[arg(long = "text")]
+        text: bool,
+
+        /// Rebuild all components, even if they are up to date
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+
+        /// Grammar of the path sequences used instead of building one, for large graphs:
+        /// a grammar written by maria grammar or maria convert,
+        /// or the prefix of bigrepair output <GRAMMAR>.R and <GRAMMAR>.C
+        #[arg(long = "grammar", verbatim_doc_comment)]
+        grammar: Option<String>,
+
+        /// MONI executable, builds the MONI index <graph>.maria/moni of the path sequences
+        #[arg(long = "moni")]
+        moni: Option<String>,
+
+        /// Build the tag array from a plain suffix array instead of the prefix-free parse.
+        /// Needs memory proportional to the length of the paths, meant for validation.
+        #[arg(long = "naive", verbatim_doc_comment)]
+        naive: bool,
+
+        /// Number of threads sampling the tag array
+        #[arg(long = "threads", default_value_t = 1)]
+        threads: usize,
+
+        /// Index the reverse complement of every path as well.
+        /// Matches of reverse complemented paths are reported on the forward paths with strand -.
+        #[arg(long = "revcomp", verbatim_doc_comment)]
+        revcomp: bool,
     },
 
     /// Find all positions of a match in a graph.
     /// Matches to the reference can be found by MONI.
-    #[command(override_usage = "maria align <GFA> <READS> > output.gaf", arg_required_else_help = true)]
+    #[command(override_usage = "maria align <INDEX> <READS> > output.gaf", arg_required_else_help = true)]
     Align {
-        /// Graph in GFA format.
-        /// For <graph>.gfa, tag array <graph>.tag and SLP grammar <graph>.slp need to be present.
-        gfa: String,
+        /// Index created by maria index, e.g. <graph>.maria.
+        /// The graph itself is not needed.
+        index: String,
 
         /// File containing reads.
         /// For <reads>.fastq, MONI outputs <reads>.mems and <reads>.pointers need to be present.
+        /// Read lengths are taken from <reads>.fastq or, if it does not exist, from <reads>.lengths of MONI ms.
         reads: String,
 
         /// Output in GAF format [default: stdout]
         #[arg(short = 'o')]
         output: Option<String>,
+
+        /// Annotate every record with the number (np:i) and names (pn:Z) of the paths
+        /// containing its node walk
+        #[arg(long = "paths")]
+        paths: bool,
+    },
+
+    /// Create an SLP grammar <graph>.slp of the concatenated path sequences
+    #[command(override_usage = "maria grammar <GFA> [-b]", arg_required_else_help = true)]
+    Grammar {
+        /// Graph in GFA format
+        gfa: String,
+
+        /// Balance the grammar, so that random access takes O(log n) time
+        #[arg(short = 'b', long = "balance")]
+        balance: bool,
+    },
+
+    /// Convert a plain SLP grammar to the binary format, which is memory-mapped by align.
+    #[command(override_usage = "maria convert <GRAMMAR> <OUTPUT> [-b]", arg_required_else_help = true)]
+    Convert {
+        /// Prefix of bigrepair output <GRAMMAR>.R and <GRAMMAR>.C,
+        /// or a plain grammar with one rule `X Y` per line
+        #[arg(verbatim_doc_comment)]
+        grammar: String,
+
+        /// Binary grammar, usually <graph>.slp.
+        output: String,
+
+        /// Balance the grammar, so that random access takes O(log n) time
+        #[arg(short = 'b', long = "balance")]
+        balance: bool,
+    },
+
+    /// Check that the index <graph>.maria was built from the graph.
+    #[command(override_usage = "maria check <GFA>", arg_required_else_help = true)]
+    Check {
+        /// Graph in GFA format.
+        /// Index <graph>.maria needs to be present.
+        gfa: String,
+    },
+
+    /// Print statistics of the index
+    #[command(override_usage = "maria stats [--grammar <GRAMMAR> [--gfa <GFA>]] [--tag <TAG>]", arg_required_else_help = true)]
+    Stats {
+        /// SLP grammar, e.g. <graph>.slp. Reports its size, height and compression
+        #[arg(long = "grammar")]
+        grammar: Option<String>,
+
+        /// Graph in GFA format, the grammar is compared to its sequences
+        #[arg(long = "gfa", requires = "grammar")]
+        gfa: Option<String>,
+
+        /// Tag array, e.g. <graph>.maria/graph.tag. Reports its runs and the nodes with most runs
+        #[arg(long = "tag")]
+        tag: Option<String>,
     },
 }
@@ -0,0 +1,45 @@
+/// Karp-Rabin fingerprints modulo the Mersenne prime 2^61 - 1.
+///
+/// The fingerprint of `s` is `sum(s[i] * BASE^(|s|-1-i))`, so the fingerprint of a concatenation
+/// `xy` is `fp(x) * BASE^|y| + fp(y)`.
+pub const MODULUS: u64 = (1 << 61) - 1;
+pub const BASE: u64 = 0x1c4c_9d2a_33f5_e8b1 % MODULUS;
+
+pub fn mul(a: u64, b: u64) -> u64 {
+    let p = a as u128 * b as u128;
+    let r = (p & MODULUS as u128) as u64 + (p >> 61) as u64;
+    return if r >= MODULUS { r - MODULUS } else { r };
+}
+
+pub fn add(a: u64, b: u64) -> u64 {
+    let r = a + b;
+    return if r >= MODULUS { r - MODULUS } else { r };
+}
+
+pub fn sub(a: u64, b: u64) -> u64 {
+    return if a >= b { a - b } else { a + MODULUS - b };
+}
+
+/// Returns BASE^e.
+pub fn power(mut e: usize) -> u64 {
+    let mut result = 1;
+    let mut base = BASE;
+    while e > 0 {
+        if e & 1 == 1 { result = mul(result, base); }
+        base = mul(base, base);
+        e >>= 1;
+    }
+    return result;
+}
+
+/// Fingerprint of a single terminal, shifted so that no terminal has a zero fingerprint.
+pub fn terminal(c: usize) -> u64 { c as u64 + 1 }
+
+#[test]
+fn fingerprint_of_concatenation() {
+    let fp = |s: &[u8]| s.iter().fold(0, |acc, &c| add(mul(acc, BASE), terminal(c as usize)));
+    let (x, y) = (b"ACGTTA", b"GGCAT");
+    let xy = [&x[..], &y[..]].concat();
+    assert_eq!(fp(&xy), add(mul(fp(x), power(y.len())), fp(y)));
+    assert_eq!(power(5), mul(power(2), power(3)));
+}
@@ -4,6 +4,8 @@ use std::fmt;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
+use crate::graph::segment_name;
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
 pub enum Direction {
     #[default]
@@ -38,6 +40,12 @@ pub struct GraphPos {
     pub pos: usize,
 }
 
+impl Display for GraphPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}:{}", self.id, self.sign, self.pos)
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseGraphPosError;
 impl From<ParseIntError> for ParseGraphPosError {
@@ -96,16 +104,20 @@ fn can_parse_graph_pos() {
     assert_eq!(gp, GraphPos{id: 10, sign: Direction::Forward, pos: 5});
     let gp = "10+:".parse::<GraphPos>();
     assert!(gp.is_err());
+    let gp: GraphPos = "10-:5".parse().unwrap();
+    assert_eq!(gp.to_string(), "10-:5");
 }
 
 impl GraphPos {
-    pub fn to_path(self) -> String {
+    /// Step of a GAF path, e.g. `>s1`, with the original name of the segment,
+    /// given the names of segments by their ids (empty if the names are the ids).
+    pub fn to_path(self, names: &[String]) -> String {
         let mut repr = String::new();
         match self.sign {
             Direction::Forward => { repr.push('>'); },
             Direction::RevComp => { repr.push('<'); }
         }
-        write!(&mut repr, "{}", self.id).unwrap();
+        write!(&mut repr, "{}", segment_name(names, self.id)).unwrap();
         return repr;
     }
 }
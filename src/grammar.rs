@@ -1,29 +1,81 @@
 use std::ops::Index;
 use std::fs::File;
-use std::io::{prelude::*, BufReader};
+use std::io::{prelude::*, BufReader, BufWriter};
 use std::path::Path;
 
+use crate::array::{Array, map_file, padding, read_u64};
+use crate::fingerprint::{self, add, mul, sub, power, BASE};
+
 #[cfg(test)]
 use std::collections::HashMap;
 
-const NTERM: usize = 256;
+/// Number of terminals in the input formats, the terminal `x` stands for the char value `x`.
+pub const NTERM: usize = 256;
+
+/// Magic bytes at the start of a binary grammar file.
+const MAGIC: &[u8; 8] = b"MARIASLP";
+const VERSION: u64 = 4;
+/// magic, version, number of terminals, root symbol, number of rules
+const HEADER_LEN: usize = 5 * 8;
 
+/// Straight-line program over a compact alphabet.
+/// Terminal `i` stands for the char `terminals[i]`, where only chars occurring in the text are kept
+/// in increasing order, e.g. ACGTN are terminals 0-4. Rule `i` defines non-terminal `i + nterm`.
+/// The root is a terminal for texts of length 1, a grammar without terminals generates the empty text.
 pub struct Grammar {
      root: usize,
-     left: Vec<usize>,
-    right: Vec<usize>,
-    sizes: Vec<usize>,
+    nterm: usize,
+     left: Array<u32>,
+    right: Array<u32>,
+    sizes: Array<usize>,
     terminals: Vec<u8>,
+    /// Karp-Rabin fingerprint of the expansion of every rule
+    fingerprints: Array<u64>,
+    /// BASE^size of every rule
+    powers: Array<u64>,
 }
 
 impl Grammar {
-    pub fn from_file<P: AsRef<Path> + ?Sized>(filename: &P) -> Self { 
+    fn new(root: usize, left: Array<u32>, right: Array<u32>, sizes: Array<usize>, terminals: Vec<u8>) -> Self {
+        let mut grammar = Grammar {
+            root, nterm: terminals.len(), left, right, sizes, terminals,
+            fingerprints: Vec::new().into(), powers: Vec::new().into()
+        };
+        grammar.compute_fingerprints();
+        return grammar;
+    }
+
+    fn compute_fingerprints(&mut self) {
+        let n = self.left.len();
+        let mut fingerprints = Vec::with_capacity(n);
+        let mut powers = Vec::with_capacity(n);
+        for i in 0..n {
+            let symbol_fingerprint = |symbol: usize| {
+                if symbol < self.nterm { (fingerprint::terminal(symbol), BASE) }
+                else { (fingerprints[symbol - self.nterm], powers[symbol - self.nterm]) }
+            };
+            let (left_fp, left_pow) = symbol_fingerprint(self.left[i] as usize);
+            let (right_fp, right_pow) = symbol_fingerprint(self.right[i] as usize);
+            fingerprints.push(add(mul(left_fp, right_pow), right_fp));
+            powers.push(mul(left_pow, right_pow));
+        }
+        self.fingerprints = fingerprints.into();
+        self.powers = powers.into();
+    }
+
+    /// Loads either a binary grammar (memory-mapped) or a plain grammar with one rule per line.
+    pub fn from_file<P: AsRef<Path> + ?Sized>(filename: &P) -> Self {
+        let mut file = File::open(filename).expect("Cannot read grammar file.");
+        let mut magic = [0; 8];
+        let is_binary = file.read_exact(&mut magic).is_ok() && &magic == MAGIC;
+        if is_binary { Self::from_binary_file(filename) } else { Self::from_plain_file(filename) }
+    }
+
+    pub fn from_plain_file<P: AsRef<Path> + ?Sized>(filename: &P) -> Self {
         let file = File::open(filename).expect("Cannot read grammar file.");
         let reader = BufReader::new(file);
 
-        let mut left = Vec::new();
-        let mut right = Vec::new();
-        let mut sizes = Vec::new();
+        let mut rules = Vec::new();
         for line in reader.lines() {
             let line = line.expect("Cannot read line in grammar file.");
             let symbols: Vec<usize> = line
@@ -32,17 +84,153 @@ impl Grammar {
                 .collect();
 
             assert_eq!(symbols.len(), 2, "Incorrect formatting of grammar file.");
-            left.push(symbols[0]);
-            right.push(symbols[1]);
+            rules.push((symbols[0], symbols[1]));
+        }
+        Self::from_rules(&rules)
+    }
+
+    /// Creates a grammar from rules `(X, Y)`, where the rule `i` defines non-terminal `i+256`.
+    /// Both X and Y need to be smaller than the defined non-terminal, the last rule is the root.
+    pub fn from_rules(rules: &[(usize, usize)]) -> Self {
+        assert!(!rules.is_empty(), "Grammar needs to contain at least one rule.");
+        Self::from_rules_with_root(rules, NTERM + rules.len() - 1)
+    }
 
-            let left_size  = if symbols[0] < NTERM { 1 } else { sizes[symbols[0] - NTERM] };
-            let right_size = if symbols[1] < NTERM { 1 } else { sizes[symbols[1] - NTERM] };
+    /// Creates a grammar generating `text` with `repair`, texts shorter than 2 have no rules.
+    pub fn from_text(text: &[u8]) -> Self {
+        match text {
+            [] => Self::with_terminals(&[], 0, Vec::new()),
+            [c] => Self::from_rules_with_root(&[], *c as usize),
+            _ => Self::from_rules(&crate::repair::repair(text)),
+        }
+    }
+
+    /// Remaps the 256 input terminals to the chars used in the rules, `root` is a symbol deriving the text.
+    fn from_rules_with_root(rules: &[(usize, usize)], root: usize) -> Self {
+        let mut used = [false; NTERM];
+        if root < NTERM { used[root] = true; }
+        for &(l, r) in rules {
+            if l < NTERM { used[l] = true; }
+            if r < NTERM { used[r] = true; }
+        }
+        let terminals: Vec<u8> = (0..NTERM).filter(|&c| used[c]).map(|c| c as u8).collect();
+        let nterm = terminals.len();
+
+        let mut new_id = [0; NTERM];
+        for (i, &c) in terminals.iter().enumerate() { new_id[c as usize] = i; }
+        let remap = |x: usize| if x < NTERM { new_id[x] } else { x - NTERM + nterm };
+        let rules: Vec<_> = rules.iter().map(|&(l, r)| (remap(l), remap(r))).collect();
+        Self::with_terminals(&rules, remap(root), terminals)
+    }
+
+    /// Creates a grammar from rules `(X, Y)` over `terminals`, where the rule `i` defines
+    /// non-terminal `i + terminals.len()`, and the symbol `root` derives the text.
+    fn with_terminals(rules: &[(usize, usize)], root: usize, terminals: Vec<u8>) -> Self {
+        let nterm = terminals.len();
+        assert!(nterm + rules.len() <= u32::MAX as usize, "Grammar has too many rules.");
+        let mut left = Vec::with_capacity(rules.len());
+        let mut right = Vec::with_capacity(rules.len());
+        let mut sizes = Vec::with_capacity(rules.len());
+        for &(l, r) in rules {
+            left.push(l as u32);
+            right.push(r as u32);
+
+            let left_size  = if l < nterm { 1 } else { sizes[l - nterm] };
+            let right_size = if r < nterm { 1 } else { sizes[r - nterm] };
             sizes.push(left_size + right_size);
         }
+        Self::new(root, left.into(), right.into(), sizes.into(), terminals)
+    }
 
-        let max_term = (NTERM - 1) as u8;
-        let terminals = (0..=max_term).collect();
-        Grammar { root: left.len() - 1, left, right, sizes, terminals}
+    /// Loads the binary output of bigrepair or of the character-based RePair with the common prefix `prefix`.
+    /// `<prefix>.R` contains the number of terminals `alph` followed by the rules as pairs of u32.
+    /// bigrepair has 256 terminals, the chars, while RePair writes only the `alph` chars occurring in the text
+    /// as a map from terminals to chars between `alph` and the rules.
+    /// `<prefix>.C` contains the final sequence as u32, which is folded into additional rules.
+    pub fn from_repair_files<P: AsRef<Path> + ?Sized>(prefix: &P) -> Self {
+        let with_suffix = |suffix: &str| {
+            let mut filename = prefix.as_ref().as_os_str().to_owned();
+            filename.push(suffix);
+            filename
+        };
+        let read = |suffix: &str| -> Vec<u8> {
+            return std::fs::read(with_suffix(suffix))
+                .unwrap_or_else(|_| panic!("Cannot read grammar file {}.", with_suffix(suffix).to_string_lossy()));
+        };
+        let to_u32s = |bytes: &[u8], suffix: &str| -> Vec<usize> {
+            assert_eq!(bytes.len() % 4, 0, "Incorrect size of grammar file {}.", with_suffix(suffix).to_string_lossy());
+            bytes.chunks_exact(4).map(|x| u32::from_le_bytes(x.try_into().unwrap()) as usize).collect()
+        };
+
+        let r = read(".R");
+        assert!(r.len() >= 4, "Grammar file {} is truncated.", with_suffix(".R").to_string_lossy());
+        let alph = u32::from_le_bytes(r[..4].try_into().unwrap()) as usize;
+        let (map, r): (Vec<u8>, _) = match alph {
+            NTERM => ((0..=u8::MAX).collect(), &r[4..]),
+            alph if alph < NTERM && r.len() >= 4 + alph => (r[4..4 + alph].to_vec(), &r[4 + alph..]),
+            alph if alph < NTERM => panic!("Grammar file {} is truncated.", with_suffix(".R").to_string_lossy()),
+            _ => panic!("Grammar file {} has {alph} terminals, only grammars over at most {NTERM} chars are supported.",
+                with_suffix(".R").to_string_lossy()),
+        };
+        let r = to_u32s(r, ".R");
+        assert!(r.len() % 2 == 0, "Incorrect formatting of grammar file {}.", with_suffix(".R").to_string_lossy());
+        // non-terminals are numbered from alph in RePair, but from NTERM here
+        let symbol = |x: usize| if x < alph { map[x] as usize } else { x - alph + NTERM };
+
+        let mut rules: Vec<_> = r.chunks_exact(2).map(|x| (symbol(x[0]), symbol(x[1]))).collect();
+        let seq = to_u32s(&read(".C"), ".C").into_iter().map(symbol).collect();
+        let root = crate::repair::fold(seq, &mut rules);
+        Self::from_rules_with_root(&rules, root)
+    }
+
+    /// Maps a grammar written by `write_binary` without copying the rules and their fingerprints.
+    pub fn from_binary_file<P: AsRef<Path> + ?Sized>(filename: &P) -> Self {
+        let file = File::open(filename).expect("Cannot read grammar file.");
+        let mmap = map_file(&file).expect("Cannot map grammar file.");
+        assert!(mmap.len() >= HEADER_LEN && &mmap[..8] == MAGIC, "Grammar file is not in binary format.");
+
+        let version = read_u64(&mmap, 1);
+        assert_eq!(version, VERSION, "Unsupported version of binary grammar, recreate it with maria grammar or maria convert.");
+        let nterm = read_u64(&mmap, 2) as usize;
+        let root = read_u64(&mmap, 3) as usize;
+        let nrules = read_u64(&mmap, 4) as usize;
+
+        let terminals = mmap[HEADER_LEN..HEADER_LEN + nterm].to_vec();
+        let mut offset = HEADER_LEN + nterm + padding(nterm);
+        let left  = Array::mapped(&mmap, offset, nrules);
+        offset += 4 * nrules + padding(4 * nrules);
+        let right = Array::mapped(&mmap, offset, nrules);
+        offset += 4 * nrules + padding(4 * nrules);
+        let sizes = Array::mapped(&mmap, offset, nrules);
+        offset += 8 * nrules;
+        let fingerprints = Array::mapped(&mmap, offset, nrules);
+        offset += 8 * nrules;
+        let powers = Array::mapped(&mmap, offset, nrules);
+        Grammar { root, nterm, left, right, sizes, terminals, fingerprints, powers }
+    }
+
+    /// Writes the grammar in the binary format, which can be memory-mapped by `from_file`.
+    /// Layout (little endian): magic, version, number of terminals, root symbol, number of rules (u64),
+    /// terminal table, left symbols (u32), right symbols (u32), expanded sizes of rules (u64),
+    /// fingerprints of rules (u64), BASE^size of rules (u64), every section is padded to 8 bytes.
+    pub fn write_binary<P: AsRef<Path> + ?Sized>(&self, filename: &P) {
+        let file = File::create(filename).expect("Cannot create grammar file.");
+        let mut writer = BufWriter::new(file);
+        let header = [VERSION, self.nterm as u64, self.root as u64, self.left.len() as u64];
+        let pad = [0; 8];
+        let symbols_padding = padding(4 * self.left.len());
+        writer.write_all(MAGIC).expect("Error writing grammar.");
+        for x in header { writer.write_all(&x.to_le_bytes()).expect("Error writing grammar."); }
+        writer.write_all(&self.terminals).expect("Error writing grammar.");
+        writer.write_all(&pad[..padding(self.nterm)]).expect("Error writing grammar.");
+        self.left.write(&mut writer).expect("Error writing grammar.");
+        writer.write_all(&pad[..symbols_padding]).expect("Error writing grammar.");
+        self.right.write(&mut writer).expect("Error writing grammar.");
+        writer.write_all(&pad[..symbols_padding]).expect("Error writing grammar.");
+        self.sizes.write(&mut writer).expect("Error writing grammar.");
+        self.fingerprints.write(&mut writer).expect("Error writing grammar.");
+        self.powers.write(&mut writer).expect("Error writing grammar.");
+        writer.flush().expect("Error writing grammar.");
     }
 
     #[cfg(test)]
@@ -77,21 +265,8 @@ impl Grammar {
         g.sort_unstable();
         println!("{:?}", g);
 
-        let mut left = Vec::new();
-        let mut right = Vec::new();
-        let mut sizes = Vec::new();
-        for (_, (l, r)) in g {
-            left.push(l);
-            right.push(r);
-
-            let left_size = if l < NTERM { 1 } else { sizes[l - NTERM] };
-            let right_size = if r < NTERM { 1 } else { sizes[r - NTERM] };
-            sizes.push(left_size + right_size);
-        }
-
-        let max_term = (NTERM - 1) as u8;
-        let terminals = (0..=max_term).collect();
-        Grammar { root: left.len() - 1, left, right, sizes, terminals }
+        let rules: Vec<_> = g.into_iter().map(|(_, rule)| rule).collect();
+        Self::from_rules(&rules)
     }
 
     #[cfg(test)]
@@ -102,22 +277,256 @@ impl Grammar {
         }
     }
 
-    pub fn len(&self) -> usize { self.sizes[self.root] }
+    pub fn len(&self) -> usize {
+        if self.terminals.is_empty() { 0 } else { self.symbol_size(self.root) }
+    }
+
+    pub fn rules_count(&self) -> usize { self.left.len() }
+
+    /// Returns chars of the terminals in increasing order.
+    pub fn alphabet(&self) -> &[u8] { &self.terminals }
+
+    /// Returns the expanded size of every rule.
+    pub fn rule_sizes(&self) -> &[usize] { &self.sizes }
+
+    /// Returns the number of bytes used by the grammar and the number of bytes of it that are memory-mapped.
+    pub fn memory_usage(&self) -> (usize, usize) {
+        let rules = [
+            (self.left.len() * 4, matches!(self.left, Array::Mapped { .. })),
+            (self.right.len() * 4, matches!(self.right, Array::Mapped { .. })),
+            (self.sizes.len() * 8, matches!(self.sizes, Array::Mapped { .. })),
+            (self.fingerprints.len() * 8, matches!(self.fingerprints, Array::Mapped { .. })),
+            (self.powers.len() * 8, matches!(self.powers, Array::Mapped { .. })),
+        ];
+        let owned = self.terminals.len();
+        let total = owned + rules.iter().map(|x| x.0).sum::<usize>();
+        let mapped = rules.iter().filter(|x| x.1).map(|x| x.0).sum();
+        return (total, mapped);
+    }
+
+    /// Returns the children of the non-terminal `symbol`.
+    fn rule(&self, symbol: usize) -> (usize, usize) {
+        let i = symbol - self.nterm;
+        (self.left[i] as usize, self.right[i] as usize)
+    }
+
+    /// Returns an iterator over the characters of the text starting at position `start`.
+    /// The grammar is descended only once, the following characters are streamed using a stack.
+    pub fn iter_from(&self, start: usize) -> Chars<'_> {
+        let mut stack = Vec::new();
+        if start < self.len() {
+            let mut symbol = self.root;
+            let mut skipped = 0;
+            while symbol >= self.nterm {
+                let (left_symbol, right_symbol) = self.rule(symbol);
+                let left_size = self.symbol_size(left_symbol);
+                if skipped + left_size > start {
+                    stack.push(right_symbol);
+                    symbol = left_symbol;
+                } else {
+                    symbol = right_symbol;
+                    skipped += left_size;
+                }
+            }
+            stack.push(symbol);
+        }
+        Chars { grammar: self, stack }
+    }
+
+    /// Returns `len` characters of the text starting at `start` (fewer at the end of the text).
+    pub fn extract(&self, start: usize, len: usize) -> Vec<u8> {
+        self.iter_from(start).take(len).collect()
+    }
+
+    /// Returns the height of the derivation tree, i.e. the worst-case number of steps of random access.
+    pub fn height(&self) -> usize {
+        if self.root < self.nterm || self.left.is_empty() { 0 } else { self.rule_heights()[self.root - self.nterm] }
+    }
+
+    /// Returns the height of every rule.
+    fn rule_heights(&self) -> Vec<usize> {
+        let mut heights: Vec<usize> = Vec::with_capacity(self.left.len());
+        for i in 0..self.left.len() {
+            let (l, r) = self.rule(i + self.nterm);
+            let left_height  = if l < self.nterm { 0 } else { heights[l - self.nterm] };
+            let right_height = if r < self.nterm { 0 } else { heights[r - self.nterm] };
+            heights.push(1 + left_height.max(right_height));
+        }
+        return heights;
+    }
+
+    /// Returns an equivalent grammar with height O(log n).
+    ///
+    /// Starting at the root, rules deeper than about twice the logarithm of their size are expanded
+    /// until the text is covered by balanced symbols, e.g. the deep concatenation of the final
+    /// RePair sequence is expanded into its symbols. These are then joined by a weight-balanced
+    /// tree of new rules. Rules no longer reachable from the root are removed.
+    pub fn balance(&self) -> Grammar {
+        if self.left.is_empty() { return Self::with_terminals(&[], self.root, self.terminals.clone()); }
+        let nterm = self.nterm;
+        let heights = self.rule_heights();
+        let is_balanced = |symbol: usize| {
+            symbol < nterm || heights[symbol - nterm] <= 2 * ceil_log2(self.sizes[symbol - nterm]) + 1
+        };
+
+        let mut units = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(symbol) = stack.pop() {
+            if is_balanced(symbol) {
+                units.push(symbol);
+            } else {
+                let (l, r) = self.rule(symbol);
+                stack.push(r);
+                stack.push(l);
+            }
+        }
+
+        let mut prefix_sizes = vec![0];
+        for &unit in &units { prefix_sizes.push(prefix_sizes.last().unwrap() + self.symbol_size(unit)); }
+
+        let mut rules: Vec<_> = (0..self.left.len()).map(|i| self.rule(i + nterm)).collect();
+        let root = join(&units, &prefix_sizes, 0, units.len(), nterm, &mut rules);
+        let (rules, root) = prune(&rules, root, nterm);
+        return Self::with_terminals(&rules, root, self.terminals.clone());
+    }
+
+    fn symbol_size(&self, symbol: usize) -> usize {
+        if symbol < self.nterm { 1 } else { self.sizes[symbol - self.nterm] }
+    }
+
+    /// Returns the fingerprint of the expansion of symbol and BASE^size.
+    fn symbol_fingerprint(&self, symbol: usize) -> (u64, u64) {
+        if symbol < self.nterm { (fingerprint::terminal(symbol), BASE) }
+        else { (self.fingerprints[symbol - self.nterm], self.powers[symbol - self.nterm]) }
+    }
+
+    /// Returns the fingerprint of the prefix of length `len` in O(height) time.
+    fn prefix_fingerprint(&self, mut len: usize) -> u64 {
+        let mut fp = 0;
+        let mut symbol = self.root;
+        while len > 0 {
+            if len == self.symbol_size(symbol) {
+                let (symbol_fp, symbol_pow) = self.symbol_fingerprint(symbol);
+                fp = add(mul(fp, symbol_pow), symbol_fp);
+                break;
+            }
+            let (left_symbol, right_symbol) = self.rule(symbol);
+            let left_size = self.symbol_size(left_symbol);
+            if len <= left_size {
+                symbol = left_symbol;
+            } else {
+                let (left_fp, left_pow) = self.symbol_fingerprint(left_symbol);
+                fp = add(mul(fp, left_pow), left_fp);
+                len -= left_size;
+                symbol = right_symbol;
+            }
+        }
+        return fp;
+    }
+
+    /// Returns the length of the longest common prefix of suffixes starting at `i` and `j`.
+    /// Uses exponential and binary search over fingerprints, i.e. O(height * log(lce)) time.
+    /// The answer is correct with high probability.
+    pub fn lce(&self, i: usize, j: usize) -> usize {
+        let max_len = self.len() - i.max(j);
+        if i == j { return max_len; }
+
+        let (fp_i, fp_j) = (self.prefix_fingerprint(i), self.prefix_fingerprint(j));
+        let equal = |l: usize| {
+            let pow = power(l);
+            sub(self.prefix_fingerprint(i + l), mul(fp_i, pow))
+                == sub(self.prefix_fingerprint(j + l), mul(fp_j, pow))
+        };
+
+        // invariant: prefixes of length lo are equal, prefixes of length hi are not
+        let mut lo = 0;
+        let mut hi = 1;
+        while hi <= max_len && equal(hi) { lo = hi; hi *= 2; }
+        hi = hi.min(max_len + 1);
+        while lo + 1 < hi {
+            let m = (lo + hi) / 2;
+            if equal(m) { lo = m; } else { hi = m; }
+        }
+        return lo;
+    }
+}
+
+/// Joins `units[lo..hi]` by a tree of new rules splitting the expanded size in halves.
+/// `prefix_sizes[i]` is the total size of `units[..i]`. Returns the symbol deriving all the units.
+fn join(
+    units: &[usize], prefix_sizes: &[usize], lo: usize, hi: usize, nterm: usize, rules: &mut Vec<(usize, usize)>
+) -> usize {
+    if hi - lo == 1 { return units[lo]; }
+    let half = (prefix_sizes[lo] + prefix_sizes[hi]) / 2;
+    let m = (lo + 1 + prefix_sizes[lo+1..hi].partition_point(|&x| x < half)).min(hi - 1);
+
+    let left = join(units, prefix_sizes, lo, m, nterm, rules);
+    let right = join(units, prefix_sizes, m, hi, nterm, rules);
+    rules.push((left, right));
+    return nterm + rules.len() - 1;
+}
+
+/// Removes rules not reachable from `root` and renumbers the rest, keeping their order.
+fn prune(rules: &[(usize, usize)], root: usize, nterm: usize) -> (Vec<(usize, usize)>, usize) {
+    let mut reachable = vec![false; rules.len()];
+    reachable[root - nterm] = true;
+    for i in (0..rules.len()).rev() {
+        if !reachable[i] { continue; }
+        let (l, r) = rules[i];
+        if l >= nterm { reachable[l - nterm] = true; }
+        if r >= nterm { reachable[r - nterm] = true; }
+    }
+
+    let mut new_id = vec![0; rules.len()];
+    let mut pruned = Vec::new();
+    let rename = |x: usize, new_id: &[usize]| if x < nterm { x } else { new_id[x - nterm] };
+    for i in 0..rules.len() {
+        if !reachable[i] { continue; }
+        let (l, r) = rules[i];
+        pruned.push((rename(l, &new_id), rename(r, &new_id)));
+        new_id[i] = nterm + pruned.len() - 1;
+    }
+    return (pruned, new_id[root - nterm]);
+}
+
+fn ceil_log2(x: usize) -> usize {
+    if x <= 1 { 0 } else { (usize::BITS - (x - 1).leading_zeros()) as usize }
+}
+
+pub struct Chars<'a> {
+    grammar: &'a Grammar,
+    /// symbols to be expanded, the next one on the top
+    stack: Vec<usize>,
+}
+
+impl Iterator for Chars<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let g = self.grammar;
+        let mut symbol = self.stack.pop()?;
+        while symbol >= g.nterm {
+            let (l, r) = g.rule(symbol);
+            self.stack.push(r);
+            symbol = l;
+        }
+        return Some(g.terminals[symbol]);
+    }
 }
 
 impl Index<usize> for Grammar {
     type Output = u8;
 
     fn index(&self, index: usize) -> &Self::Output {
-        let mut symbol = self.root + NTERM;
+        let mut symbol = self.root;
         let mut skipped = 0;
-        while symbol >= NTERM {
-            let left_symbol = self.left[symbol - NTERM];
-            let left_size = if left_symbol < NTERM { 1 } else { self.sizes[left_symbol - NTERM] };
+        while symbol >= self.nterm {
+            let (left_symbol, right_symbol) = self.rule(symbol);
+            let left_size = self.symbol_size(left_symbol);
             if skipped + left_size > index {
                 symbol = left_symbol;
             } else {
-                symbol = self.right[symbol - NTERM];
+                symbol = right_symbol;
                 skipped += left_size;
             }
         }
@@ -125,3 +534,11 @@ impl Index<usize> for Grammar {
     }
 }
 
+#[test]
+fn terminals_are_compacted() {
+    let g = Grammar::from_rules(&crate::repair::repair(b"ACGTNACGTTTGCA"));
+    assert_eq!(g.terminals, b"ACGNT");
+    assert_eq!(g.nterm, 5);
+    assert_eq!(g.extract(0, g.len()), b"ACGTNACGTTTGCA");
+    assert!(g[3] > g[2] && g[4] < g[3]);
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{read, File};
+use std::iter::zip;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str;
+
+use crate::gp::{Direction, GraphPos};
+use crate::reverse_complement;
+
+/// Segments and paths of a GFA file.
+/// Paths are read from P-lines, W-lines (walks) and groups of GFA 2 in the order of the file.
+/// Segments named by numbers have these numbers as ids, otherwise segments are numbered in the order of the file
+/// and their names are kept in `segment_names`.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    segments: Vec<(usize, Vec<u8>)>,
+    /// position of every segment in `segments` by its id
+    index: HashMap<usize, usize>,
+    /// names of segments by their ids, empty if the names are the ids
+    pub segment_names: Vec<String>,
+    pub paths: Vec<GraphPath>,
+    /// the second half of the paths are the reverse complements of the first half, see `with_reverse_paths`
+    pub reverse_paths: bool,
+}
+
+/// Path of the graph, the walk of a W-line keeps its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphPath {
+    pub name: String,
+    pub steps: Vec<GraphPos>,
+    /// overlap of every step with the previous step, 0 for the first step,
+    /// i.e. the offset of the first character of the step in the text
+    pub overlaps: Vec<usize>,
+    pub walk: Option<Walk>,
+}
+
+/// Metadata of a W-line: `W <sample> <haplotype> <contig> <start> <end> <walk>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    pub sample: String,
+    pub haplotype: usize,
+    pub contig: String,
+    /// interval of the contig covered by the walk, `*` if unknown
+    pub interval: Option<(usize, usize)>,
+}
+
+impl Walk {
+    /// Name of the walk in the PanSN convention `sample#haplotype#contig`,
+    /// followed by `:start-end` if the interval is known.
+    pub fn name(&self) -> String {
+        let name = format!("{}#{}#{}", self.sample, self.haplotype, self.contig);
+        return match self.interval {
+            Some((start, end)) => format!("{name}:{start}-{end}"),
+            None => name,
+        };
+    }
+}
+
+impl Graph {
+    /// Reads a graph in GFA 1 or GFA 2, which is recognized by its header or by lines of GFA 2 records.
+    /// Ordered (O-lines) and unordered (U-lines) groups of GFA 2 are read as paths of their segments,
+    /// references to edges are skipped, groups containing only edges are skipped as well.
+    /// Overlaps of consecutive steps are taken from the overlaps of P-lines or, if a path gives `*`,
+    /// from the L-lines joining the steps. Only overlaps of the form `nM` are supported.
+    /// Segments defined more than once, or whose numeric names denote the same id like `1` and `01`, are refused.
+    pub fn from_file<P: AsRef<Path>>(gfa: P) -> Self {
+        let gfa = gfa.as_ref();
+        let content = read(gfa).unwrap_or_else(|_| panic!("Cannot read file {}", gfa.display()));
+        let gfa2 = content.split(|&c| c == b'\n').any(|line|
+            line.starts_with(b"H\tVN:Z:2") || [b"E\t", b"O\t", b"U\t", b"F\t", b"G\t"].iter().any(|r| line.starts_with(*r))
+        );
+
+        let mut segments = Vec::new();
+        let mut edges = HashSet::new();
+        let mut links = HashMap::new();
+        let mut paths = Vec::new();
+        for (i, line) in content.split(|&c| c == b'\n').enumerate() {
+            let line = String::from_utf8_lossy(line);
+            let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+            match parse_line(&fields, gfa2) {
+                Some(Line::Segment(name, sequence)) => segments.push((name, sequence)),
+                Some(Line::Path(name, references, overlaps, walk)) => paths.push((name, references, overlaps, walk)),
+                Some(Line::Edge(name)) => { edges.insert(name); },
+                Some(Line::Link(from, to, overlap)) => {
+                    // the link is also traversed in the reverse direction
+                    links.insert(((to.0.clone(), flip(to.1)), (from.0.clone(), flip(from.1))), overlap);
+                    links.insert((from, to), overlap);
+                },
+                Some(Line::Other) => {},
+                None => panic!("Error parsing GFA file, line {} is incorrect: {line}", i + 1),
+            }
+        }
+
+        let numbers: Option<Vec<usize>> = segments.iter().map(|(name, _)| name.parse().ok()).collect();
+        let ids: HashMap<String, usize> = match &numbers {
+            Some(numbers) => zip(segments.iter().map(|(name, _)| name.clone()), numbers.iter().copied()).collect(),
+            None => segments.iter().enumerate().map(|(id, (name, _))| (name.clone(), id)).collect(),
+        };
+        let mut graph = Graph::default();
+        if numbers.is_none() { graph.segment_names = segments.iter().map(|(name, _)| name.clone()).collect(); }
+        let mut names = HashMap::new();
+        for (name, sequence) in segments {
+            let id = ids[&name];
+            if let Some(other) = names.insert(id, name.clone()) {
+                if other == name { panic!("Segment {name} is defined more than once."); }
+                panic!("Segments {other} and {name} have the same id {id}.");
+            }
+            graph.index.insert(id, graph.segments.len());
+            graph.segments.push((id, sequence));
+        }
+        for (name, references, overlaps, walk) in paths {
+            let references: Vec<_> = references.into_iter().filter(|(reference, _)| !edges.contains(reference)).collect();
+            // a group of only edges has no sequence
+            if references.is_empty() { continue; }
+            let mut steps: Vec<GraphPos> = Vec::with_capacity(references.len());
+            let mut step_overlaps = Vec::with_capacity(references.len());
+            for (k, (reference, sign)) in references.iter().enumerate() {
+                let Some(&id) = ids.get(reference) else {
+                    panic!("Path {name} visits segment {reference}, which is not in the graph.");
+                };
+                let overlap = match (k, &overlaps) {
+                    (0, _) => 0,
+                    (_, Some(overlaps)) => overlaps[k-1],
+                    (_, None) => match links.get(&(references[k-1].clone(), (reference.clone(), *sign))) {
+                        Some(Some(overlap)) => *overlap,
+                        Some(None) => panic!("Path {name}: overlap of segments {} and {reference} is not supported, only overlaps of the form nM are.",
+                            references[k-1].0),
+                        None => 0,
+                    },
+                };
+                if k > 0 && overlap >= graph.sequence(id).len().min(graph.sequence(steps[k-1].id).len() + 1) {
+                    panic!("Path {name}: overlap {overlap}M of segments {} and {reference} is longer than the segments.", references[k-1].0);
+                }
+                steps.push(GraphPos { id, sign: *sign, pos: 0 });
+                step_overlaps.push(overlap);
+            }
+            graph.paths.push(GraphPath { name, steps, overlaps: step_overlaps, walk });
+        }
+        return graph;
+    }
+
+    /// Appends the reverse complement of every path, named `<path>_rev`, which visits the nodes of the path
+    /// in the opposite order and orientation, so that every path is indexed in both orientations.
+    pub fn with_reverse_paths(mut self) -> Self {
+        assert!(!self.reverse_paths, "Reverse paths were already added.");
+        let reverse: Vec<GraphPath> = self.paths.iter().map(|path| {
+            let steps = path.steps.iter().rev().map(|step| GraphPos { sign: flip(step.sign), ..*step }).collect();
+            // a step overlaps the step that followed it on the forward path
+            let overlaps = std::iter::once(0).chain(path.overlaps.iter().skip(1).rev().copied()).collect();
+            GraphPath { name: format!("{}_rev", path.name), steps, overlaps, walk: None }
+        }).collect();
+        self.paths.extend(reverse);
+        self.reverse_paths = true;
+        return self;
+    }
+
+    /// Names and sequences of the segments in the order of the file.
+    pub fn segments(&self) -> &[(usize, Vec<u8>)] {
+        return &self.segments;
+    }
+
+    /// Sequence of the segment in the forward direction.
+    pub fn sequence(&self, id: usize) -> &[u8] {
+        return &self.segments[self.index[&id]].1;
+    }
+
+    /// Concatenation of the path sequences, i.e. the text indexed by the tag array, grammar and MONI.
+    /// The overlap of a step with the previous step is not repeated.
+    pub fn text(&self) -> Vec<u8> {
+        let mut text = Vec::new();
+        for (step, &overlap) in self.paths.iter().flat_map(|path| zip(&path.steps, &path.overlaps)) {
+            match step.sign {
+                Direction::Forward => text.extend_from_slice(&self.sequence(step.id)[overlap..]),
+                Direction::RevComp => text.extend(&reverse_complement(self.sequence(step.id))[overlap..]),
+            }
+        }
+        return text;
+    }
+
+    /// Overlaps of the steps of all paths with their previous steps, in the order of the steps in the text.
+    pub fn overlaps(&self) -> Vec<usize> {
+        return self.paths.iter().flat_map(|path| path.overlaps.iter().copied()).collect();
+    }
+
+    /// Writes the segments and all paths as P-lines of GFA 1 without overlaps,
+    /// the input of the prefix-free parse, which reads no other kinds of paths.
+    /// Steps overlapping the previous step visit new segments without the overlap,
+    /// so that the paths spell the same text.
+    pub fn write_paths(&self, gfa: &Path) {
+        let mut segments = self.segments.clone();
+        // trimmed segments are numbered after all segments of the graph
+        let first_id = self.segments.iter().map(|(id, _)| id + 1).max().unwrap_or(0);
+        let mut trimmed: HashMap<(GraphPos, usize), usize> = HashMap::new();
+        let paths: Vec<Vec<String>> = self.paths.iter().map(|path| zip(&path.steps, &path.overlaps).map(|(&step, &overlap)| {
+            if overlap == 0 { return format!("{}{}", step.id, step.sign); }
+            let next_id = first_id + trimmed.len();
+            let id = *trimmed.entry((step, overlap)).or_insert_with(|| {
+                let sequence = self.sequence(step.id);
+                let sequence = match step.sign {
+                    Direction::Forward => &sequence[overlap..],
+                    Direction::RevComp => &sequence[..sequence.len() - overlap],
+                };
+                segments.push((next_id, sequence.to_vec()));
+                next_id
+            });
+            format!("{id}{}", step.sign)
+        }).collect()).collect();
+
+        let mut writer = BufWriter::new(File::create(gfa)
+            .unwrap_or_else(|_| panic!("Cannot open file {}", gfa.display())));
+        writeln!(writer, "H\tVN:Z:1.0").expect("Error writing graph.");
+        for (name, sequence) in &segments {
+            writeln!(writer, "S\t{name}\t{}", str::from_utf8(sequence).unwrap()).expect("Error writing graph.");
+        }
+        for (path, steps) in zip(&self.paths, paths) {
+            writeln!(writer, "P\t{}\t{}\t*", path.name, steps.join(",")).expect("Error writing graph.");
+        }
+        writer.flush().expect("Error writing.");
+    }
+}
+
+/// Name of the segment with the id, given the names of segments by their ids, see `Graph::segment_names`.
+pub fn segment_name(names: &[String], id: usize) -> String {
+    return names.get(id).cloned().unwrap_or_else(|| id.to_string());
+}
+
+enum Line {
+    Segment(String, Vec<u8>),
+    /// path, walk or group with its references to segments (and edges in GFA 2) in the given orientation
+    /// and the overlaps of consecutive references, if given
+    Path(String, Vec<(String, Direction)>, Option<Vec<usize>>, Option<Walk>),
+    /// link of GFA 1 with the length of its overlap, None if the overlap is not supported
+    Link((String, Direction), (String, Direction), Option<usize>),
+    /// edge of GFA 2, groups may refer to it
+    Edge(String),
+    /// header, links and other lines that do not describe sequences
+    Other,
+}
+
+/// Parses the tab-separated fields of a line, returns None if the line is incorrect.
+/// Segments of GFA 2 have a length field and segments without a sequence are incorrect.
+fn parse_line(fields: &[&str], gfa2: bool) -> Option<Line> {
+    let line = match *fields {
+        ["S", name, sequence, ..] if !gfa2 && sequence != "*" => Line::Segment(name.to_string(), sequence.as_bytes().to_vec()),
+        ["S", name, len, sequence, ..] if gfa2 && sequence != "*" => {
+            len.parse::<usize>().ok()?;
+            Line::Segment(name.to_string(), sequence.as_bytes().to_vec())
+        },
+        ["P", name, steps, ref overlaps @ ..] => {
+            let references: Vec<_> = steps.split(',').map(parse_oriented).collect::<Option<_>>()?;
+            let overlaps = match overlaps.first() {
+                None | Some(&"*") => None,
+                Some(overlaps) => {
+                    let overlaps: Vec<_> = overlaps.split(',').map(parse_overlap).collect::<Option<_>>()?;
+                    if overlaps.len() + 1 != references.len() { return None; }
+                    Some(overlaps)
+                },
+            };
+            Line::Path(name.to_string(), references, overlaps, None)
+        },
+        ["L", from, from_sign, to, to_sign, overlap, ..] if !gfa2 => {
+            let from = (from.to_string(), from_sign.parse().ok()?);
+            let to = (to.to_string(), to_sign.parse().ok()?);
+            Line::Link(from, to, parse_overlap(overlap))
+        },
+        ["W", sample, haplotype, contig, start, end, steps, ..] => {
+            let interval = match (start, end) {
+                ("*", _) | (_, "*") => None,
+                _ => Some((start.parse().ok()?, end.parse().ok()?)),
+            };
+            let walk = Walk { sample: sample.to_string(), haplotype: haplotype.parse().ok()?, contig: contig.to_string(), interval };
+            Line::Path(walk.name(), parse_walk(steps)?, None, Some(walk))
+        },
+        ["E", name, ..] if gfa2 => Line::Edge(name.to_string()),
+        ["O", name, references, ..] if gfa2 => {
+            let references = references.split(' ').map(parse_oriented).collect::<Option<_>>()?;
+            Line::Path(name.to_string(), references, None, None)
+        },
+        // members of unordered groups have no orientation
+        ["U", name, references, ..] if gfa2 => {
+            let references = references.split(' ').map(|reference| (reference.to_string(), Direction::Forward)).collect();
+            Line::Path(name.to_string(), references, None, None)
+        },
+        ["S" | "P" | "W" | "L" | "E" | "O" | "U", ..] => return None,
+        _ => Line::Other,
+    };
+    return Some(line);
+}
+
+/// Parses an overlap of the form `nM`, `*` is no overlap.
+/// Returns None for other CIGAR strings, which are not supported.
+fn parse_overlap(cigar: &str) -> Option<usize> {
+    if cigar == "*" { return Some(0); }
+    return cigar.strip_suffix('M')?.parse().ok();
+}
+
+/// Opposite orientation.
+pub fn flip(sign: Direction) -> Direction {
+    return match sign {
+        Direction::Forward => Direction::RevComp,
+        Direction::RevComp => Direction::Forward,
+    };
+}
+
+/// Parses a reference followed by its orientation, e.g. `s1+`.
+fn parse_oriented(reference: &str) -> Option<(String, Direction)> {
+    let (name, sign) = reference.split_at(reference.len().checked_sub(1)?);
+    if name.is_empty() { return None; }
+    return Some((name.to_string(), sign.parse().ok()?));
+}
+
+/// Parses the steps of a walk, e.g. `>s1<s2>s3`.
+fn parse_walk(walk: &str) -> Option<Vec<(String, Direction)>> {
+    if !walk.starts_with(['>', '<']) { return None; }
+    let mut steps = Vec::new();
+    for (i, c) in walk.char_indices().filter(|(_, c)| *c == '>' || *c == '<') {
+        let end = walk[i+1..].find(['>', '<']).map_or(walk.len(), |j| i + 1 + j);
+        if end == i + 1 { return None; }
+        let sign = if c == '>' { Direction::Forward } else { Direction::RevComp };
+        steps.push((walk[i+1..end].to_string(), sign));
+    }
+    return Some(steps);
+}
+
+#[test]
+fn walk_is_parsed() {
+    let steps = parse_walk(">10<s2>3").unwrap();
+    let step = |name: &str, sign| (name.to_string(), sign);
+    assert_eq!(steps, vec![step("10", Direction::Forward), step("s2", Direction::RevComp), step("3", Direction::Forward)]);
+    assert_eq!(parse_walk("10<2"), None);
+    assert_eq!(parse_walk(">1<"), None);
+}
@@ -0,0 +1,281 @@
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+use crate::checksum::{checksum, file_checksum};
+use crate::layout::Layout;
+use crate::graph::Graph;
+use crate::{create_grammar, create_tag, create_text, import_grammar};
+use crate::{tag, triggers};
+
+const VERSION: u64 = 7;
+const MANIFEST: &str = "manifest.tsv";
+/// Components of the index and their files inside the index directory.
+const COMPONENTS: [(&str, &str); 5] = [
+    ("layout", "graph.layout"),
+    ("text", "graph.fna"),
+    ("grammar", "graph.slp"),
+    ("tag", "graph.tag"),
+    ("triggers", "triggers.txt"),
+];
+/// Prefix of the MONI index inside the index directory.
+const MONI: &str = "moni";
+/// Run-length BWT with thresholds written by `moni build`, read by `moni ms` and `moni mems`.
+const MONI_INDEX: &str = "moni.thrbv.ms";
+
+/// Index bundle, a directory containing everything `maria align` needs:
+/// the layout of the graph, the grammar, the tag array and the triggers used for its construction,
+/// together with the concatenated path sequences, the reference for MONI.
+/// The manifest records checksums of the graph and of every component.
+#[derive(Debug)]
+pub struct Index {
+    dir: PathBuf,
+    /// checksum of the GFA file the index was built from
+    pub graph_checksum: u64,
+}
+
+/// Options of the index construction.
+#[derive(Debug, Default, Clone)]
+pub struct IndexOptions {
+    /// write the tag array in the text format
+    pub text: bool,
+    /// rebuild all components, even if they are up to date
+    pub force: bool,
+    /// MONI executable used to build the MONI index of the path sequences
+    pub moni: Option<PathBuf>,
+    /// build the tag array from a plain suffix array instead of the prefix-free parse
+    pub naive: bool,
+    /// number of threads sampling the tag array
+    pub threads: usize,
+    /// average length of phrases of automatically selected triggers
+    pub phrase_len: usize,
+    /// index the reverse complement of every path as well
+    pub revcomp: bool,
+    /// existing grammar of the path sequences, which is imported instead of building one
+    pub grammar: Option<PathBuf>,
+}
+
+/// Default location of the index of the graph, <graph>.maria.
+pub fn index_path(gfa: &Path) -> PathBuf {
+    return gfa.with_extension("maria");
+}
+
+/// Checksum of the graph and file name and checksum of every component recorded in a manifest.
+struct Manifest {
+    graph_checksum: u64,
+    /// the paths are indexed in both orientations
+    revcomp: bool,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    file: String,
+    checksum: u64,
+    /// size and modification time of the file, not recorded for MONI
+    stamp: Option<Stamp>,
+}
+
+/// Size of a file and its modification time in nanoseconds since the epoch.
+type Stamp = (u64, u128);
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    return Some((metadata.len(), modified.as_nanos()));
+}
+
+impl Manifest {
+    fn read(dir: &Path) -> Result<Self, String> {
+        let manifest = read_to_string(dir.join(MANIFEST)).map_err(|_| format!(
+            "{} is not an index, the manifest {MANIFEST} is missing. Create it with:\n\n\tmaria index <graph>.gfa -t <triggers.txt>\n",
+            dir.display()
+        ))?;
+        let mut lines = manifest.lines().map(|line| line.split('\t').collect::<Vec<_>>());
+
+        match lines.next().as_deref() {
+            Some(["maria-index", version]) if *version == VERSION.to_string() => {},
+            _ => return Err(format!("Unsupported version of index {}, recreate it with maria index.", dir.display())),
+        }
+        let (graph_checksum, revcomp) = match lines.next().as_deref() {
+            Some(["graph", _, checksum, strands @ ("forward" | "both")]) => (parse_checksum(checksum)?, *strands == "both"),
+            _ => return Err(format!("Manifest of index {} does not describe the graph.", dir.display())),
+        };
+        let mut entries = Vec::new();
+        for entry in lines {
+            let incorrect = || format!("Manifest entry {} is incorrect.", entry.join("\t"));
+            let (name, file, checksum, stamp) = match entry[..] {
+                [name, file, checksum] => (name, file, checksum, None),
+                [name, file, checksum, size, modified] => {
+                    let stamp = size.parse().ok().zip(modified.parse().ok()).ok_or_else(incorrect)?;
+                    (name, file, checksum, Some(stamp))
+                },
+                _ => return Err(incorrect()),
+            };
+            entries.push(Entry { name: name.to_string(), file: file.to_string(), checksum: parse_checksum(checksum)?, stamp });
+        }
+        return Ok(Manifest { graph_checksum, revcomp, entries });
+    }
+
+    fn get(&self, name: &str) -> Option<&Entry> {
+        return self.entries.iter().find(|entry| entry.name == name);
+    }
+
+    /// Returns the paths of the components, or an error if one of them is missing.
+    fn components(&self, dir: &Path) -> Result<Vec<(&Entry, PathBuf)>, String> {
+        let mut components = Vec::new();
+        for (name, file) in COMPONENTS {
+            let Some(entry) = self.get(name) else {
+                return Err(format!("Manifest of index {} does not contain {name}.", dir.display()));
+            };
+            let path = dir.join(&entry.file);
+            if entry.file != file || !path.exists() {
+                return Err(format!("File {} of index {} does not exist.", path.display(), dir.display()));
+            }
+            components.push((entry, path));
+        }
+        return Ok(components);
+    }
+}
+
+impl Index {
+    /// Builds the components from the graph and writes the manifest.
+    /// Components that are up to date according to the manifest of an existing index are kept, unless `force` is set.
+    /// If `moni` is given, the MONI index of the path sequences is built with that executable.
+    /// If no trigger file is given, triggers are selected from the path sequences.
+    /// If `grammar` is given, that grammar is imported instead of building one with RePair.
+    /// With `revcomp`, the reverse complements of the paths are indexed after the paths.
+    pub fn create(gfa: &Path, triggers: Option<&Path>, dir: &Path, options: &IndexOptions) -> Self {
+        println!("Creating index {}", dir.display());
+        fs::create_dir_all(dir).unwrap_or_else(|_| panic!("Cannot create directory {}", dir.display()));
+        let index = Index { dir: dir.to_path_buf(), graph_checksum: file_checksum(gfa) };
+
+        let recorded = Manifest::read(dir).ok();
+        let previous = recorded.as_ref().filter(|manifest|
+            !options.force && manifest.graph_checksum == index.graph_checksum && manifest.revcomp == options.revcomp
+        );
+        let is_current = |name: &str| {
+            let Some(entry) = previous.and_then(|manifest| manifest.get(name)) else { return false };
+            let path = dir.join(&entry.file);
+            let current = path.exists() && file_checksum(&path) == entry.checksum;
+            if current { println!("Skipping {name}, {} is up to date.", path.display()); }
+            return current;
+        };
+
+        let mut graph = Graph::from_file(gfa);
+        if options.revcomp { graph = graph.with_reverse_paths(); }
+        if !is_current("layout") { Layout::new(&graph).write(&index.layout()); }
+        if !is_current("text") { create_text(&graph, &index.text()); }
+        if !is_current("grammar") {
+            match &options.grammar {
+                Some(grammar) => import_grammar(&graph, grammar, &index.grammar()),
+                None => create_grammar(&graph, &index.grammar(), false),
+            }
+        }
+
+        let mut text = None;
+        let triggers = match triggers {
+            Some(triggers) => fs::read(triggers).unwrap_or_else(|_| panic!("Cannot read file {}", triggers.display())),
+            None => {
+                let selected = triggers::select(text.insert(graph.text()), options.phrase_len)
+                    .unwrap_or_else(|message| panic!("{message}"));
+                println!("Selected {} triggers for average phrase length {}.", selected.len(), options.phrase_len);
+                triggers::serialize(&selected)
+            }
+        };
+        let same_triggers = previous.and_then(|manifest| manifest.get("triggers"))
+            .is_some_and(|entry| entry.checksum == checksum(&triggers));
+        let same_format = index.tag().exists() && tag::read_header(&index.tag()).is_none() == options.text;
+        if !(same_triggers && same_format && is_current("tag")) {
+            fs::write(index.triggers(), &triggers).expect("Cannot write triggers to the index.");
+            {
+                // the text is dropped before the construction, whose memory is bounded by the prefix-free parse
+                let text = text.take().unwrap_or_else(|| graph.text());
+                let stats = triggers::parse_stats(&text, &triggers::parse(&triggers));
+                println!("Triggers split the paths of length {} into {} phrases, the dictionary has {} phrases of total length {}.",
+                    text.len(), stats.phrases, stats.dictionary_phrases, stats.dictionary_len);
+            }
+            create_tag(&graph, index.graph_checksum, &index.triggers(), &index.tag(), options);
+        }
+
+        let strands = if options.revcomp { "both" } else { "forward" };
+        let mut manifest = format!("maria-index\t{VERSION}\ngraph\t{}\t{:016x}\t{strands}\n", gfa.display(), index.graph_checksum);
+        for (name, file) in COMPONENTS {
+            let path = dir.join(file);
+            let (size, modified) = stamp(&path).unwrap_or_else(|| panic!("Cannot read metadata of {}", path.display()));
+            manifest.push_str(&format!("{name}\t{file}\t{:016x}\t{size}\t{modified}\n", file_checksum(&path)));
+        }
+
+        // the MONI entry records the checksum of the text it was built from,
+        // an up-to-date MONI index is kept even if no MONI executable is given
+        let text_checksum = file_checksum(&index.text());
+        let moni_current = |manifest: Option<&Manifest>| {
+            let recorded = manifest.and_then(|manifest| manifest.get(MONI)).map(|entry| entry.checksum);
+            recorded == Some(text_checksum) && dir.join(MONI_INDEX).exists()
+        };
+        let has_moni = match &options.moni {
+            Some(_) if moni_current(previous) => {
+                println!("Skipping {MONI}, {} is up to date.", dir.join(MONI).display());
+                true
+            },
+            Some(moni) => {
+                create_moni(moni, &index.text(), &dir.join(MONI));
+                true
+            },
+            None => moni_current(recorded.as_ref()),
+        };
+        if has_moni { manifest.push_str(&format!("{MONI}\t{MONI}\t{text_checksum:016x}\n")); }
+
+        fs::write(dir.join(MANIFEST), manifest).expect("Cannot write the manifest.");
+        println!("Index successfully created.");
+        return index;
+    }
+
+    /// Opens the index and verifies the sizes and modification times of all components.
+    /// Only components modified after the index was created are checksummed, use `verify` to checksum all of them.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let manifest = Manifest::read(dir)?;
+        for (entry, path) in manifest.components(dir)? {
+            let current = stamp(&path);
+            let same_size = current.zip(entry.stamp).is_some_and(|(current, recorded)| current.0 == recorded.0);
+            if !same_size || (current != entry.stamp && file_checksum(&path) != entry.checksum) {
+                return Err(mismatch(&path));
+            }
+        }
+        return Ok(Index { dir: dir.to_path_buf(), graph_checksum: manifest.graph_checksum });
+    }
+
+    /// Verifies the checksums of all components.
+    pub fn verify(&self) -> Result<(), String> {
+        let manifest = Manifest::read(&self.dir)?;
+        for (entry, path) in manifest.components(&self.dir)? {
+            if file_checksum(&path) != entry.checksum { return Err(mismatch(&path)); }
+        }
+        return Ok(());
+    }
+
+    pub fn layout(&self) -> PathBuf { self.dir.join(COMPONENTS[0].1) }
+    pub fn text(&self) -> PathBuf { self.dir.join(COMPONENTS[1].1) }
+    pub fn grammar(&self) -> PathBuf { self.dir.join(COMPONENTS[2].1) }
+    pub fn tag(&self) -> PathBuf { self.dir.join(COMPONENTS[3].1) }
+    pub fn triggers(&self) -> PathBuf { self.dir.join(COMPONENTS[4].1) }
+}
+
+/// f: fna -> moni
+fn create_moni(moni: &Path, fna: &Path, prefix: &Path) {
+    println!("Creating MONI index {}", prefix.display());
+    let status = Command::new(moni)
+        .args(["build", "-f", "-r"]).arg(fna).arg("-o").arg(prefix)
+        .status()
+        .unwrap_or_else(|_| panic!("Cannot run {}", moni.display()));
+    if !status.success() { panic!("MONI failed with {status}."); }
+}
+
+fn mismatch(path: &Path) -> String {
+    return format!("File {} does not match the manifest, it was modified or belongs to another index.", path.display());
+}
+
+fn parse_checksum(checksum: &str) -> Result<u64, String> {
+    return u64::from_str_radix(checksum, 16).map_err(|_| format!("Checksum {checksum} in the manifest is incorrect."));
+}
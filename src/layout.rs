@@ -0,0 +1,108 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::array::{padding, read_u64};
+use crate::gp::{Direction, GraphPos};
+use crate::graph::Graph;
+use crate::path_coordinates;
+
+/// Magic bytes at the start of a layout file.
+const MAGIC: &[u8; 8] = b"MARIALAY";
+const VERSION: u64 = 4;
+/// magic, version, number of paths, number of nodes on paths, number of segment names, reverse paths
+const HEADER_LEN: usize = 6 * 8;
+
+/// Coordinates of the paths and the nodes on paths in the text, i.e. the concatenated path sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub path_starts: Vec<usize>,
+    pub path_names: Vec<String>,
+    /// start of every node occurrence on paths, followed by the length of the text as a sentinel
+    pub node_starts: Vec<usize>,
+    /// node occurrences at their first position
+    pub node_names: Vec<GraphPos>,
+    /// overlaps of node occurrences with the previous nodes, see `GraphPath::overlaps`
+    pub node_overlaps: Vec<usize>,
+    /// names of segments by their ids, empty if the names are the ids
+    pub segment_names: Vec<String>,
+    /// the second half of the paths are the reverse complements of the first half
+    pub reverse_paths: bool,
+}
+
+impl Layout {
+    pub fn new(graph: &Graph) -> Self {
+        let (path_starts, path_names, node_starts, node_names) = path_coordinates(graph);
+        return Layout {
+            path_starts, path_names, node_starts, node_names, node_overlaps: graph.overlaps(),
+            segment_names: graph.segment_names.clone(), reverse_paths: graph.reverse_paths,
+        };
+    }
+
+    /// Length of the text.
+    pub fn text_len(&self) -> usize {
+        return *self.node_starts.last().unwrap();
+    }
+
+    /// Writes the layout in the binary format:
+    /// header, path starts, node starts including the sentinel, node ids with the sign in the lowest bit,
+    /// overlaps of nodes with the previous nodes, path names followed by segment names, separated by newlines and padded to 8 bytes.
+    pub fn write(&self, filename: &Path) {
+        let mut writer = BufWriter::new(File::create(filename)
+            .unwrap_or_else(|_| panic!("Cannot open file {}", filename.display())));
+        let nodes = self.node_names.iter().map(|gp| (gp.id << 1 | (gp.sign == Direction::RevComp) as usize) as u64);
+        let header = [
+            VERSION, self.path_starts.len() as u64, self.node_names.len() as u64, self.segment_names.len() as u64,
+            self.reverse_paths as u64,
+        ];
+
+        writer.write_all(MAGIC).expect("Error writing layout.");
+        for x in header.into_iter()
+            .chain(self.path_starts.iter().map(|&x| x as u64))
+            .chain(self.node_starts.iter().map(|&x| x as u64))
+            .chain(nodes)
+            .chain(self.node_overlaps.iter().map(|&x| x as u64)) {
+            writer.write_all(&x.to_le_bytes()).expect("Error writing layout.");
+        }
+        let names = self.path_names.iter().chain(&self.segment_names).cloned().collect::<Vec<_>>().join("\n");
+        writer.write_all(names.as_bytes()).expect("Error writing layout.");
+        writer.write_all(&[0; 8][..padding(names.len())]).expect("Error writing layout.");
+        writer.flush().expect("Error writing.");
+    }
+
+    pub fn read(filename: &Path) -> Self {
+        let buffer = std::fs::read(filename).unwrap_or_else(|_| panic!("Cannot read file {}", filename.display()));
+        assert!(buffer.len() >= HEADER_LEN && &buffer[..8] == MAGIC, "File {} is not a layout.", filename.display());
+        assert_eq!(read_u64(&buffer, 1), VERSION, "Unsupported version of layout, recreate the index with maria index.");
+        let paths = read_u64(&buffer, 2) as usize;
+        let nodes = read_u64(&buffer, 3) as usize;
+        let segments = read_u64(&buffer, 4) as usize;
+        let reverse_paths = read_u64(&buffer, 5) == 1;
+        let names_offset = 8 * (6 + paths + 3 * nodes + 1);
+        assert!(buffer.len() >= names_offset, "Layout file {} is truncated.", filename.display());
+
+        let path_starts = (6..6 + paths).map(|i| read_u64(&buffer, i) as usize).collect();
+        let node_starts = (6 + paths..7 + paths + nodes).map(|i| read_u64(&buffer, i) as usize).collect();
+        let node_names = (7 + paths + nodes..7 + paths + 2 * nodes).map(|i| {
+            let x = read_u64(&buffer, i) as usize;
+            let sign = if x & 1 == 1 { Direction::RevComp } else { Direction::Forward };
+            GraphPos { id: x >> 1, sign, pos: 0 }
+        }).collect();
+        let node_overlaps = (7 + paths + 2 * nodes..7 + paths + 3 * nodes).map(|i| read_u64(&buffer, i) as usize).collect();
+        let names = std::str::from_utf8(&buffer[names_offset..]).expect("Path names are not valid UTF-8.");
+        let mut names = names.trim_end_matches('\0').split('\n').map(str::to_string);
+        let path_names = names.by_ref().take(paths).collect();
+        let segment_names = names.take(segments).collect();
+
+        return Layout { path_starts, path_names, node_starts, node_names, node_overlaps, segment_names, reverse_paths };
+    }
+}
+
+#[test]
+fn layout_is_read_back() {
+    let dir = crate::tests::TempDir::new("layout");
+    let filename = dir.join("graph.layout");
+    let layout = Layout::new(&Graph::from_file("data/real/SARS-CoV2.5.gfa"));
+    layout.write(&filename);
+    assert_eq!(Layout::read(&filename), layout);
+}
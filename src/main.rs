@@ -1,5 +1,4 @@
 use clap::Parser;
-use gfa::parser::GFAParser;
 use core::panic;
 use std::collections::HashMap;
 use std::fs::File;
@@ -8,46 +7,62 @@ use std::io::Write;
 use std::io::stdout;
 use std::path::Path;
 use std::path::PathBuf;
-use std::str;
 use std::usize;
 use std::iter::zip;
+use std::thread;
 
+mod array;
+mod bits;
+mod check;
+mod checksum;
 mod cli;
+mod fingerprint;
 mod gp;
+mod graph;
 mod pred;
 mod grammar;
+mod index;
+mod layout;
 mod mem;
+mod naive;
+mod repair;
+mod stats;
+mod tag;
+mod triggers;
 
 use gp::GraphPos as GraphPos;
+use gp::Direction;
 use grammar::Grammar;
-use mem::MEMReader;
+use graph::Graph;
+use index::{Index, IndexOptions};
+use layout::Layout;
+use mem::{LengthReader, MEMReader};
 use pred::Predecessor;
+use tag::{Run, TagWriter};
 use cli::Args;
 
 fn main() {
     let args = Args::parse();
 
     match &args.command {
-        cli::Commands::Index { gfa, triggers } => {
+        cli::Commands::Index { gfa, triggers, phrase_len, text, force, grammar, moni, naive, threads, revcomp, .. } => {
             let gfa = PathBuf::from(gfa);
             if !gfa.exists() { panic!("File {} does not exist.", gfa.display()); }
-            let triggers = PathBuf::from(triggers);
-            if !triggers.exists() { panic!("File {} does not exist.", triggers.display()); }
+            let triggers = triggers.as_ref().map(PathBuf::from);
+            if let Some(triggers) = &triggers {
+                if !triggers.exists() { panic!("File {} does not exist.", triggers.display()); }
+            }
 
-            let tag = gfa.with_extension("tag");
-            // println!("f: {gfa:?} {triggers:?} -> {tag:?}");
-            create_tag(&gfa, &triggers, &tag)
+            let options = IndexOptions {
+                text: *text, force: *force, moni: moni.as_ref().map(PathBuf::from), naive: *naive, threads: *threads, phrase_len: *phrase_len,
+                revcomp: *revcomp, grammar: grammar.as_ref().map(PathBuf::from),
+            };
+            Index::create(&gfa, triggers.as_deref(), &index::index_path(&gfa), &options);
         },
-        cli::Commands::Align { gfa, reads, output } => {
-            let gfa = PathBuf::from(gfa);
-            if !gfa.exists() { panic!("File {} does not exist.", gfa.display()); }
-            let tag = gfa.with_extension("tag");
-            if !tag.exists() { panic!(
-                "File {} does not exist. Create it with:\n\n\tmaria index {} -t <triggers.txt>.\n",
-                tag.display(), gfa.display()
-            )}
-            let slp = gfa.with_extension("slp");
-            if !slp.exists() { panic!("File {} does not exist.", slp.display()) }
+        cli::Commands::Align { index, reads, output, paths } => {
+            let index = PathBuf::from(index);
+            if !index.exists() { panic!("Index {} does not exist.", index.display()); }
+            let index = Index::open(&index).unwrap_or_else(|message| panic!("{message}"));
 
             let reads = PathBuf::from(reads);
             let mems = reads.with_extension("mems");
@@ -55,70 +70,216 @@ fn main() {
 
             if !mems.exists() { panic!("File {} does not exist.", mems.display()) }
             if !ptrs.exists() { panic!("File {} does not exist.", ptrs.display()) }
+            let lengths = if reads.exists() {
+                LengthReader::reads(&reads)
+            } else {
+                let lengths = reads.with_extension("lengths");
+                if !lengths.exists() { panic!("Neither {} nor {} exists.", reads.display(), lengths.display()) }
+                LengthReader::lengths(&lengths)
+            };
 
-            // println!("f: {gfa:?} {tag:?} {slp:?} {mems:?} {ptrs:?} -> {output:?}");
+            // println!("f: {index:?} {mems:?} {ptrs:?} -> {output:?}");
             if let Some(filename) = output {
                 let out = BufWriter::new(
                     File::create(filename).expect("Cannot create output file.")
                 );
-                align(&gfa, &tag, &slp, &mems, &ptrs, out);
+                align(&index, &mems, &ptrs, lengths, *paths, out);
             } else {
                 let out = stdout().lock();
-                align(&gfa, &tag, &slp, &mems, &ptrs, out);
+                align(&index, &mems, &ptrs, lengths, *paths, out);
+            }
+        },
+        cli::Commands::Check { gfa } => {
+            let gfa = PathBuf::from(gfa);
+            if !gfa.exists() { panic!("File {} does not exist.", gfa.display()); }
+            let index = index::index_path(&gfa);
+            if !index.exists() { panic!("Index {} does not exist.", index.display()) }
+
+            match check::check_index(&gfa, &index) {
+                Ok(()) => println!("Index is consistent with the graph."),
+                Err(message) => {
+                    eprintln!("{message}");
+                    std::process::exit(1);
+                }
+            }
+        },
+        cli::Commands::Stats { grammar, gfa, tag } => {
+            let gfa = gfa.as_ref().map(PathBuf::from);
+            if let Some(gfa) = &gfa {
+                if !gfa.exists() { panic!("File {} does not exist.", gfa.display()); }
+            }
+            if let Some(grammar) = grammar {
+                let grammar = PathBuf::from(grammar);
+                if !grammar.exists() { panic!("File {} does not exist.", grammar.display()); }
+                stats::grammar_stats(&grammar, gfa.as_deref(), stdout().lock());
+            }
+            if let Some(tag) = tag {
+                let tag = PathBuf::from(tag);
+                if !tag.exists() { panic!("File {} does not exist.", tag.display()); }
+                stats::tag_stats(&tag, stdout().lock());
             }
+        },
+        cli::Commands::Grammar { gfa, balance } => {
+            let gfa = PathBuf::from(gfa);
+            if !gfa.exists() { panic!("File {} does not exist.", gfa.display()); }
+
+            let slp = gfa.with_extension("slp");
+            create_grammar(&Graph::from_file(&gfa), &slp, *balance)
+        },
+        cli::Commands::Convert { grammar, output, balance } => {
+            let grammar = PathBuf::from(grammar);
+            let output = PathBuf::from(output);
+
+            println!("Converting grammar {} to {}", grammar.display(), output.display());
+            let mut grammar = read_grammar(&grammar);
+            if *balance { grammar = grammar.balance(); }
+            grammar.write_binary(&output);
+            println!("Grammar with height {} successfully converted.", grammar.height());
         }
     }
 }
 
-/// f: gfa triggers -> tag
-fn create_tag(gfa: &Path, triggers: &Path, tag: &Path) {
+/// f: graph triggers -> tag
+/// The binary tag array records the checksum of the GFA file of the graph.
+fn create_tag(graph: &Graph, graph_checksum: u64, triggers: &Path, tag: &Path, options: &IndexOptions) {
     println!("Creating tag array {}", tag.display());
-    let (_, _, node_starts, node_names) = process_graph(gfa);
-    let (ssa, stag) = get_sampled_arrays(&gfa, &triggers, &node_starts, &node_names);
-
-    let mut writer: BufWriter<File> = BufWriter::new(File::create(tag)
-        .unwrap_or_else(|_| panic!("Cannot open file {}", tag.display())));
-    for i in 0..ssa.len() {
-        writeln!(writer, "{}\t{}{}:{}", ssa[i],
-            stag[i].id, stag[i].sign, stag[i].pos // implement Display for tag?
-        ).expect("Error while writing tags.");
+    let (_, _, node_starts, node_names) = path_coordinates(graph);
+    let node_overlaps = graph.overlaps();
+
+    let mut writer = if options.text {
+        TagWriter::text(tag)
+    } else {
+        let text_len = *node_starts.last().unwrap();
+        let max_id = node_names.iter().map(|gp| gp.id).max().unwrap_or(0);
+        let max_pos = zip(node_starts.windows(2), &node_overlaps).map(|(w, overlap)| w[1] - w[0] + overlap).max().unwrap_or(1).saturating_sub(1);
+        TagWriter::binary(tag, text_len, graph_checksum, max_id, max_pos)
+    };
+    let emit = |run| writer.push(run);
+    if options.naive {
+        naive::sample_tag_array(graph, &node_starts, &node_names, &node_overlaps, options.threads, emit);
+    } else {
+        // the prefix-free parse reads only P-lines, so walks are passed as paths
+        let paths = TempFile(tag.with_extension("paths.gfa"));
+        graph.write_paths(&paths.0);
+        sample_tag_array(&paths.0, triggers, &node_starts, &node_names, &node_overlaps, options.threads, emit);
     }
-    writer.flush().expect("Error writing.");
+    writer.finish();
     println!("Tag array successfully created.");
 }
 
-/// f: gfa tag slp mems ptrs -> output
+/// Temporary file removed on drop, also if the construction using it panics.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// f: graph -> slp
+fn create_grammar(graph: &Graph, slp: &Path, balance: bool) {
+    println!("Creating grammar {}", slp.display());
+    let text = graph.text();
+    let mut grammar = Grammar::from_text(&text);
+    if balance { grammar = grammar.balance(); }
+    assert!(grammar.extract(0, grammar.len()) == text, "Grammar does not generate the path sequences.");
+
+    grammar.write_binary(slp);
+    println!("Grammar with height {} successfully created.", grammar.height());
+}
+
+/// f: graph grammar -> slp
+/// Writes an existing grammar of the path sequences, e.g. built by bigrepair, in the binary format,
+/// after verifying that it generates the path sequences.
+fn import_grammar(graph: &Graph, grammar: &Path, slp: &Path) {
+    println!("Importing grammar {}", grammar.display());
+    let text = graph.text();
+    let imported = read_grammar(grammar);
+    assert!(imported.len() == text.len() && imported.iter_from(0).eq(text.iter().copied()),
+        "Grammar {} does not generate the path sequences.", grammar.display());
+
+    imported.write_binary(slp);
+    println!("Grammar with height {} successfully imported.", imported.height());
+}
+
+/// Reads the output of bigrepair with the prefix `grammar`, if it exists, otherwise a binary or plain grammar.
+fn read_grammar(grammar: &Path) -> Grammar {
+    let with_suffix = |suffix: &str| PathBuf::from(format!("{}{suffix}", grammar.display()));
+    if with_suffix(".R").exists() && with_suffix(".C").exists() {
+        return Grammar::from_repair_files(grammar);
+    }
+    if !grammar.exists() { panic!("File {} does not exist.", grammar.display()); }
+    return Grammar::from_file(grammar);
+}
+
+/// f: graph -> fna
+/// Writes the path sequences as FASTA, one record per path,
+/// whose concatenation is the text indexed by the grammar and the tag array.
+fn create_text(graph: &Graph, fna: &Path) {
+    println!("Creating path sequences {}", fna.display());
+    let (mut path_starts, path_names, _, _) = path_coordinates(graph);
+    let text = graph.text();
+    path_starts.push(text.len());
+
+    let mut writer = BufWriter::new(File::create(fna)
+        .unwrap_or_else(|_| panic!("Cannot open file {}", fna.display())));
+    for (i, name) in path_names.iter().enumerate() {
+        writeln!(writer, ">{name}").expect("Error writing path sequences.");
+        writer.write_all(&text[path_starts[i]..path_starts[i+1]]).expect("Error writing path sequences.");
+        writeln!(writer).expect("Error writing path sequences.");
+    }
+    writer.flush().expect("Error writing.");
+}
+
+/// f: index mems ptrs lengths -> output
 fn align<T>(
-    gfa: &Path, tag: &Path, grammar: &Path, mems: &Path, ptrs: &Path, mut output: T
+    index: &Index, mems: &Path, ptrs: &Path, mut lengths: LengthReader, paths: bool, mut output: T
 ) where
     T: Write
 {
-    let (_, _, node_starts, node_names) = process_graph(gfa);
-    let (ssa, stag) = read_tag_array(tag);
-    let grammar = Grammar::from_file(grammar);
+    let layout = Layout::read(&index.layout());
+    let text_len = layout.text_len();
+    let Layout { path_starts, path_names, node_starts, node_names, node_overlaps, segment_names, reverse_paths } = layout;
+    let path_steps = paths.then(|| PathSteps::new(&path_starts, &node_starts, &node_names, reverse_paths));
+    if let Err(message) = tag::check_header(&index.tag(), index.graph_checksum, text_len) { panic!("{message}"); }
+    let (ssa, stag, _) = tag::read_tag_array(&index.tag());
+    let grammar = Grammar::from_file(&index.grammar());
+    assert_eq!(grammar.len(), text_len, "Grammar does not generate the text of the index.");
     let mem_reader = MEMReader::new(mems, ptrs);
 
     for (read_id, mems) in mem_reader {
+        let (length_id, read_len) = lengths.next()
+            .unwrap_or_else(|| panic!("Read {read_id} has no length."));
+        if length_id != read_id { panic!("Reads are not in the same order as MEMs!\n{read_id}\n{length_id}") }
         for mem in mems {
             let (sa_values, positions) = get_graph_positions(&grammar, &mem, &stag, &ssa);
             for (sa, _) in zip(sa_values, positions) {
-                let (path, plen, pstart, pend) = extract_path(sa, mem.0, &node_starts, &node_names);
+                let len = clip_to_path(sa, mem.0, &path_starts, text_len);
+                let (sa, strand) = forward_strand(sa, len, &path_starts, text_len, reverse_paths);
+                let (path, plen, pstart, pend) = extract_path(sa, len, &node_starts, &node_names, &node_overlaps, &segment_names);
                 #[allow(clippy::write_literal)] {
-                    writeln!(output, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    write!(output, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                         read_id,        // string:  Query sequence name
-                        150,            // int:     Query sequence length
+                        read_len,       // int:     Query sequence length
                         mem.1,          // int:     Query start (0-based; closed)
-                        mem.1 + mem.0,  // int:     Query end (0-based; open)
-                        '+',            // char:    Strand relative to the path: "+" or "-"
+                        mem.1 + len,    // int:     Query end (0-based; open)
+                        strand,         // char:    Strand relative to the path: "+" or "-"
                         path,           // string:  Path matching
                         plen,           // int:     Path length
                         pstart,         // int:     Start position on the path (0-based; closed)
                         pend,           // int:     End position on the path (0-based; open)
-                        mem.0,          // int:     Number of residue matches
-                        mem.0,          // int:     Alignment block length
+                        len,            // int:     Number of residue matches
+                        len,            // int:     Alignment block length
                         60              // int:     Mapping quality (0-255; 255 for missing)
                     ).expect("Error writing output");
                 }
+                if let Some(path_steps) = &path_steps {
+                    let walk = &node_names[node_starts.argpred(sa)..=node_starts.argpred(sa + len - 1)];
+                    let containing = path_steps.paths_containing(walk);
+                    let names: Vec<&str> = containing.iter().map(|&p| path_names[p].as_str()).collect();
+                    write!(output, "\tnp:i:{}\tpn:Z:{}", names.len(), names.join(",")).expect("Error writing output");
+                }
+                writeln!(output).expect("Error writing output");
             }
         }
     }
@@ -135,34 +296,92 @@ fn align<T>(
 //     return stdout();
 // }
 
-fn read_tag_array(tag: &Path) -> (Vec<usize>, Vec<GraphPos>) {
-    use std::fs::read_to_string;
-    let res: (Vec<_>, Vec<_>) = read_to_string(tag).unwrap().lines().map(|line| {
-        let z: Vec<_> = line.split('\t').collect();
-        if z.len() != 2 { panic!("Tag array line {line} is incorrect.") }
-        let sa_value: usize = z[0].parse().unwrap();
-        let tag_value: GraphPos = z[1].parse().unwrap();
-        (sa_value, tag_value)
-    }).unzip();
-    return res;
+/// Returns the length of the part of a match of length `len` that lies on the path where it starts.
+/// The paths are concatenated without separators, so a match may continue into the next path.
+fn clip_to_path(sa_value: usize, len: usize, path_starts: &Vec<usize>, text_len: usize) -> usize {
+    let p = path_starts.argpred(sa_value);
+    let end = path_starts.get(p + 1).copied().unwrap_or(text_len);
+    return len.min(end - sa_value);
+}
+
+/// Returns the start of a match of length `len` on the forward path and its strand.
+/// A match of a reverse complemented path is mapped to the forward path, where it ends
+/// at the same distance from the end of the path as it starts from the start of the reverse path.
+fn forward_strand(
+    sa_value: usize, len: usize, path_starts: &Vec<usize>, text_len: usize, reverse_paths: bool
+) -> (usize, char) {
+    let p = path_starts.argpred(sa_value);
+    let forward_paths = path_starts.len() / 2;
+    if !reverse_paths || p < forward_paths { return (sa_value, '+'); }
+
+    let end = path_starts.get(p + 1).copied().unwrap_or(text_len);
+    return (path_starts[p - forward_paths] + end - sa_value - len, '-');
+}
+
+/// Steps of all paths, indexed by the oriented node they visit.
+struct PathSteps<'a> {
+    steps: HashMap<(usize, Direction), Vec<usize>>,
+    step_paths: Vec<usize>,
+    node_names: &'a [GraphPos],
+    forward_paths: usize,
+    reverse_paths: bool,
+}
+
+impl<'a> PathSteps<'a> {
+    fn new(path_starts: &Vec<usize>, node_starts: &[usize], node_names: &'a [GraphPos], reverse_paths: bool) -> Self {
+        let mut steps: HashMap<(usize, Direction), Vec<usize>> = HashMap::new();
+        for (i, node) in node_names.iter().enumerate() {
+            steps.entry((node.id, node.sign)).or_default().push(i);
+        }
+        let step_paths = node_starts[..node_names.len()].iter().map(|&start| path_starts.argpred(start)).collect();
+        let forward_paths = if reverse_paths { path_starts.len() / 2 } else { path_starts.len() };
+        return PathSteps { steps, step_paths, node_names, forward_paths, reverse_paths };
+    }
+
+    /// Returns the sorted forward paths visiting the nodes of the walk consecutively in the same orientation.
+    /// A walk found on a reverse complemented path is reported on its forward path.
+    /// Without reverse paths, the walk is also searched in the opposite order and orientation.
+    fn paths_containing(&self, walk: &[GraphPos]) -> Vec<usize> {
+        let mut paths = self.paths_visiting(walk);
+        if !self.reverse_paths {
+            let reverse: Vec<GraphPos> = walk.iter().rev().map(|gp| GraphPos { sign: graph::flip(gp.sign), ..*gp }).collect();
+            paths.extend(self.paths_visiting(&reverse));
+        }
+        paths.sort();
+        paths.dedup();
+        return paths;
+    }
+
+    /// Returns the forward paths visiting the nodes of the walk consecutively in the given orientation.
+    fn paths_visiting(&self, walk: &[GraphPos]) -> Vec<usize> {
+        let same = |a: &GraphPos, b: &GraphPos| a.id == b.id && a.sign == b.sign;
+        return self.steps.get(&(walk[0].id, walk[0].sign)).into_iter().flatten()
+            .filter(|&&i| i + walk.len() <= self.node_names.len())
+            .filter(|&&i| self.step_paths[i] == self.step_paths[i + walk.len() - 1])
+            .filter(|&&i| zip(&self.node_names[i..i + walk.len()], walk).all(|(a, b)| same(a, b)))
+            .map(|&i| self.step_paths[i] % self.forward_paths)
+            .collect();
+    }
 }
 
 fn extract_path(
     sa_value: usize, seq_len: usize,
-    node_starts: &Vec<usize>, node_names: &[GraphPos]
+    node_starts: &Vec<usize>, node_names: &[GraphPos], node_overlaps: &[usize], segment_names: &[String]
 ) -> (String, usize, usize, usize) {
     let mut i = node_starts.argpred(sa_value);
     let start = node_starts[i];
+    // the first node of the path starts with its overlap with the previous node
+    let offset = node_overlaps[i];
 
-    let pstart = sa_value - start;
+    let pstart = sa_value - start + offset;
     let pend = pstart + seq_len;
 
     let mut path = String::new();
-    while node_starts[i] < start + pend {
-        path.push_str(&node_names[i].to_path());
+    while node_starts[i] < sa_value + seq_len {
+        path.push_str(&node_names[i].to_path(segment_names));
         i += 1;
     }
-    let plen = node_starts[i] - start;
+    let plen = node_starts[i] - start + offset;
 
     return (path, plen, pstart, pend);
 }
@@ -174,18 +393,13 @@ fn extract_path_correctly_handles_the_end() {
     let node_starts = vec![0, 10];
     let node_names = vec![GraphPos::default()];
 
-    extract_path(sa_value, seq_len, &node_starts, &node_names);
+    extract_path(sa_value, seq_len, &node_starts, &node_names, &[0], &[]);
 }
 
-fn process_graph<P: AsRef<Path>>(filename: P) -> (
+/// Returns starts and names of paths and starts and names of nodes on paths in the concatenated path sequences.
+fn path_coordinates(graph: &Graph) -> (
     Vec<usize>, Vec<String>, Vec<usize>, Vec<GraphPos>
 ) {
-    let parser: GFAParser<usize, ()> = GFAParser::new();
-    let graph = parser.parse_file(filename).expect("Error parsing GFA file.");
-
-    let mut seg_len = HashMap::new();
-    for seg in &graph.segments { seg_len.insert(seg.name, seg.sequence.len()); }
-
     let mut path_starts = Vec::new();
     let mut path_names = Vec::new();
     let mut node_starts = Vec::new();
@@ -194,15 +408,11 @@ fn process_graph<P: AsRef<Path>>(filename: P) -> (
     let mut start = 0;
     for path in &graph.paths {
         path_starts.push(start);
-        path_names.push(str::from_utf8(&path.path_name).unwrap().to_string());
+        path_names.push(path.name.clone());
 
-        let segments: Vec<GraphPos> =
-            str::from_utf8(&path.segment_names).unwrap()
-            .split(',').map(|x| x.parse().unwrap()).collect();
-
-        for node in segments {
+        for (&node, overlap) in zip(&path.steps, &path.overlaps) {
             node_starts.push(start);
-            start += *seg_len.get(&node.id).unwrap();
+            start += graph.sequence(node.id).len() - overlap;
             node_names.push(node);
         }
     }
@@ -210,43 +420,79 @@ fn process_graph<P: AsRef<Path>>(filename: P) -> (
     return (path_starts, path_names, node_starts, node_names);
 }
 
-/// Returns sampled suffix array and sampled tag array.
-/// Both array are sampled at the starts and ends of run boundaries of the tag array.
-fn get_sampled_arrays<P: AsRef<Path>>(
-    gfa: P, triggers: P, node_starts: &Vec<usize>, node_names: &[GraphPos]
-) -> (Vec<usize>, Vec<GraphPos>) {
-    let pfdata = pfg::pf::PFData::from_graph(gfa.as_ref().to_str().unwrap(), triggers.as_ref().to_str().unwrap());
-    let mut iterator = pfdata.iter();
+fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().map(|&c| match c {
+        b'A' => b'T', b'C' => b'G', b'G' => b'C', b'T' => b'A',
+        b'a' => b't', b'c' => b'g', b'g' => b'c', b't' => b'a',
+        _ => c,
+    }).collect()
+}
 
-    let mut sampled_tag = Vec::new();
-    let mut sampled_suf = Vec::new();
+/// Number of suffixes sampled by one thread at a time.
+const CHUNK_LEN: usize = 1 << 16;
 
-    let (sa, _, _) = iterator.next().unwrap();
-    let i = node_starts.argpred(sa);
-    let gp = GraphPos{pos: sa - node_starts[i], ..node_names[i]};
-    sampled_tag.push(gp);
-    sampled_suf.push(sa);
+/// Calls `emit` for every run of the tag array in the suffix array order.
+/// Runs are sampled as the suffixes are enumerated, only chunks of suffixes are kept in memory.
+fn sample_tag_array<P: AsRef<Path>, Q: AsRef<Path>, F: FnMut(Run)>(
+    gfa: P, triggers: Q, node_starts: &Vec<usize>, node_names: &[GraphPos], node_overlaps: &[usize], threads: usize, emit: F
+) {
+    let pfdata = pfg::pf::PFData::from_graph(gfa.as_ref().to_str().unwrap(), triggers.as_ref().to_str().unwrap());
+    let suffixes = pfdata.iter().map(|(sa, _, _)| sa);
+    sample_runs_in_chunks(suffixes, node_starts, node_names, node_overlaps, threads, CHUNK_LEN, emit);
+}
 
-    let mut old_gp = gp;
-    let mut old_sa = sa;
+/// Samples the suffixes like `sample_runs`, splitting them into chunks of `chunk_len` suffixes
+/// that are sampled by `threads` threads in parallel.
+fn sample_runs_in_chunks<I: Iterator<Item = usize>, F: FnMut(Run)>(
+    suffixes: I, node_starts: &Vec<usize>, node_names: &[GraphPos], node_overlaps: &[usize],
+    threads: usize, chunk_len: usize, mut emit: F
+) {
+    if threads <= 1 { return sample_runs(suffixes, node_starts, node_names, node_overlaps, emit); }
+
+    let mut pending = None;
+    let mut suffixes = suffixes.peekable();
+    while suffixes.peek().is_some() {
+        let chunks: Vec<Vec<usize>> = (0..threads)
+            .map(|_| suffixes.by_ref().take(chunk_len).collect())
+            .filter(|chunk: &Vec<usize>| !chunk.is_empty())
+            .collect();
+        let sampled: Vec<Vec<Run>> = thread::scope(|scope| {
+            let handles: Vec<_> = chunks.iter().map(|chunk| scope.spawn(|| {
+                let mut runs = Vec::new();
+                sample_runs(chunk.iter().copied(), node_starts, node_names, node_overlaps, |run| runs.push(run));
+                runs
+            })).collect();
+            handles.into_iter().map(|handle| handle.join().expect("Sampling thread panicked.")).collect()
+        });
+        // runs with the same tag on both sides of a chunk border are merged
+        for run in sampled.into_iter().flatten() { push_run(&mut pending, run, &mut emit); }
+    }
+    if let Some(run) = pending { emit(run); }
+}
 
-    for (sa, _, _) in iterator {
+/// Calls `emit` for every run of the tag array of the suffixes given in the suffix array order.
+fn sample_runs<I: Iterator<Item = usize>, F: FnMut(Run)>(
+    suffixes: I, node_starts: &Vec<usize>, node_names: &[GraphPos], node_overlaps: &[usize], mut emit: F
+) {
+    let mut pending = None;
+    for sa in suffixes {
         let i = node_starts.argpred(sa);
-        let gp = GraphPos{pos: sa - node_starts[i], ..node_names[i]};
-        if old_gp != gp {
-            sampled_tag.push(old_gp);
-            sampled_suf.push(old_sa);
-            sampled_tag.push(gp);
-            sampled_suf.push(sa);
-            old_gp = gp;
-        }
-        old_sa = sa;
+        let tag = GraphPos{pos: sa - node_starts[i] + node_overlaps[i], ..node_names[i]};
+        push_run(&mut pending, Run{start: sa, end: sa, tag, len: 1}, &mut emit);
     }
+    if let Some(run) = pending { emit(run); }
+}
 
-    sampled_tag.push(old_gp);
-    sampled_suf.push(old_sa);
-
-    return (sampled_suf, sampled_tag);
+/// Extends the pending run by the following run if they have the same tag,
+/// otherwise emits the pending run and replaces it.
+fn push_run<F: FnMut(Run)>(pending: &mut Option<Run>, run: Run, emit: &mut F) {
+    match pending {
+        Some(last) if last.tag == run.tag => {
+            last.end = run.end;
+            last.len += run.len;
+        },
+        _ => if let Some(last) = pending.replace(run) { emit(last); },
+    }
 }
 
 fn get_graph_positions(
@@ -292,8 +538,7 @@ fn lce(grammar: &Grammar, s1: usize, s2: usize) -> (usize, bool) {
     let n = grammar.len();
     if s1 == s2 { return (n-s1, false); }    // the same is not smaller
 
-    let mut l = 0;
-    while s1+l < n && s2+l < n && grammar[s1+l] == grammar[s2+l] { l += 1; }
+    let l = grammar.lce(s1, s2);
     if s1+l == n { return (l, true); }
     if s2+l == n { return (l, false); }
     if grammar[s1+l] < grammar[s2+l] { return (l, true); }
@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::path::Path;
 use std::io::{BufReader, BufRead, Lines};
+use std::iter::Peekable;
 
 pub struct MEMReader {
     mem_lines: Lines<BufReader<File>>,
@@ -27,7 +28,7 @@ impl Iterator for MEMReader {
         let id1 = id1.unwrap().expect("Error reading line in MEM.");
         let id2 = id2.unwrap().expect("Error reading line in PTR.");
         if id1 != id2 { panic!("IDs are not the same!\n{id1}\n{id2}") }
-        let id = id1.strip_prefix('>').expect("Id does not start with >").to_owned();
+        let id = read_id(id1.strip_prefix('>').expect("Id does not start with >"));
 
         let mem = self.mem_lines.next()
             .expect("Expected mem line.")
@@ -50,6 +51,93 @@ impl Iterator for MEMReader {
     }
 }
 
+/// Reads names and lengths of reads, either from the reads in FASTA or FASTQ format
+/// or from the matching statistics lengths written by MONI, which contain a length for every position of a read.
+/// Sequences and lengths may span several lines.
+pub struct LengthReader {
+    lines: Peekable<Lines<BufReader<File>>>,
+    format: Format,
+}
+
+enum Format {
+    Fasta,
+    Fastq,
+    Lengths,
+}
+
+impl LengthReader {
+    /// Reads the lengths of reads in FASTA or FASTQ format, which is recognized by the first character of the file.
+    pub fn reads<P: AsRef<Path> + ?Sized>(filename: &P) -> Self {
+        let mut lines = open_lines(filename.as_ref()).peekable();
+        let format = match lines.peek() {
+            Some(Ok(line)) if line.starts_with('@') => Format::Fastq,
+            Some(Ok(line)) if line.starts_with('>') || line.is_empty() => Format::Fasta,
+            None => Format::Fasta,
+            _ => panic!("File {} is neither in FASTA nor in FASTQ format.", filename.as_ref().display()),
+        };
+        Self{lines, format}
+    }
+
+    /// Reads the lengths of reads from the matching statistics lengths of `moni ms`.
+    pub fn lengths<P: AsRef<Path> + ?Sized>(filename: &P) -> Self {
+        Self{lines: open_lines(filename.as_ref()).peekable(), format: Format::Lengths}
+    }
+
+    fn next_line(&mut self, expected: &str) -> String {
+        return self.lines.next()
+            .unwrap_or_else(|| panic!("Expected {expected} line."))
+            .expect("Error reading read lengths.");
+    }
+}
+
+impl Iterator for LengthReader {
+    type Item = (String, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = loop {
+            let line = self.lines.next()?.expect("Error reading read lengths.");
+            if !line.trim().is_empty() { break line; }
+        };
+        let prefix = if let Format::Fastq = self.format { '@' } else { '>' };
+        let id = header.strip_prefix(prefix)
+            .unwrap_or_else(|| panic!("Read header does not start with {prefix}:\n{header}"));
+
+        let mut len = 0;
+        match self.format {
+            Format::Fastq => {
+                // the quality may start with @ or +, so it is read until it is as long as the sequence
+                loop {
+                    let line = self.next_line("sequence or +");
+                    if line.starts_with('+') { break; }
+                    len += line.trim_end().len();
+                }
+                let mut quality = 0;
+                while quality < len { quality += self.next_line("quality").trim_end().len(); }
+            },
+            Format::Fasta | Format::Lengths => {
+                while let Some(line) = self.lines.next_if(|line| !matches!(line, Ok(line) if line.starts_with('>'))) {
+                    let line = line.expect("Error reading read lengths.");
+                    len += match self.format {
+                        Format::Lengths => line.split_whitespace().count(),
+                        _ => line.trim_end().len(),
+                    };
+                }
+            },
+        }
+        return Some((read_id(id), len));
+    }
+}
+
+/// Name of a read given its header without the leading > or @, i.e. the header up to the first whitespace.
+pub fn read_id(header: &str) -> String {
+    return header.split_whitespace().next().unwrap_or_default().to_owned();
+}
+
+fn open_lines(filename: &Path) -> Lines<BufReader<File>> {
+    let file = File::open(filename).unwrap_or_else(|_| panic!("Cannot open read lengths file {}.", filename.display()));
+    return BufReader::new(file).lines();
+}
+
 #[allow(non_snake_case)]
 fn parse_MEM(mem: &str) -> (usize, usize) {
     let mem = &mem[1..mem.len()-1];
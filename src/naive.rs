@@ -0,0 +1,49 @@
+use crate::gp::GraphPos;
+use crate::graph::Graph;
+use crate::tag::Run;
+use crate::{sample_runs_in_chunks, CHUNK_LEN};
+
+/// Returns the suffix array of the text by prefix doubling.
+/// A suffix that is a prefix of another suffix is smaller.
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = text.iter().map(|&c| c as usize + 1).collect();
+    let mut new_rank = vec![0; n];
+
+    // rank of the suffix at i by its first 2k characters
+    let key = |rank: &[usize], i: usize, k: usize| (rank[i], if i + k < n { rank[i + k] } else { 0 });
+
+    let mut k = 1;
+    while k < n {
+        sa.sort_unstable_by_key(|&i| key(&rank, i, k));
+        new_rank[sa[0]] = 1;
+        for j in 1..n {
+            let different = key(&rank, sa[j-1], k) != key(&rank, sa[j], k);
+            new_rank[sa[j]] = new_rank[sa[j-1]] + different as usize;
+        }
+        std::mem::swap(&mut rank, &mut new_rank);
+        if rank[sa[n-1]] == n { break; }
+        k *= 2;
+    }
+    return sa;
+}
+
+/// Calls `emit` for every run of the tag array computed from the suffix array of the concatenated path sequences.
+/// Gives the same result as `sample_tag_array` without the prefix-free parse,
+/// but needs memory proportional to the length of the text.
+pub fn sample_tag_array<F: FnMut(Run)>(
+    graph: &Graph, node_starts: &Vec<usize>, node_names: &[GraphPos], node_overlaps: &[usize], threads: usize, emit: F
+) {
+    let text = graph.text();
+    sample_runs_in_chunks(suffix_array(&text).into_iter(), node_starts, node_names, node_overlaps, threads, CHUNK_LEN, emit);
+}
+
+#[test]
+fn suffix_array_is_sorted() {
+    let text = b"GATTACATTAGA";
+    let sa = suffix_array(text);
+    let mut expected: Vec<usize> = (0..text.len()).collect();
+    expected.sort_by_key(|&i| &text[i..]);
+    assert_eq!(sa, expected);
+}
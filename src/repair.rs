@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::grammar::NTERM;
+
+/// Builds rules of a straight-line program generating `text` with a RePair-like scheme.
+///
+/// Every round counts the frequencies of adjacent pairs and replaces, from left to right,
+/// pairs occurring at least twice, preferring the more frequent one of two overlapping pairs.
+/// Rounds are repeated until no pair repeats, the remaining sequence is then folded
+/// into a balanced binary tree by `fold`.
+/// Rules are in the format accepted by `Grammar::from_rules`, texts shorter than 2 have no rules.
+pub fn repair(text: &[u8]) -> Vec<(usize, usize)> {
+    if text.len() < 2 { return Vec::new(); }
+    // symbols are stored as u32 to halve the memory of the working sequence
+    let mut seq: Vec<u32> = text.iter().map(|&x| x as u32).collect();
+    let mut rules = Vec::new();
+
+    loop {
+        let freq = pair_frequencies(&seq);
+        if freq.values().all(|&f| f < 2) { break; }
+        let frequency = |i: usize| if i + 1 < seq.len() { freq[&(seq[i], seq[i+1])] } else { 0 };
+
+        let mut rule_id: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut next = Vec::with_capacity(seq.len());
+        let mut i = 0;
+        while i < seq.len() {
+            let f = frequency(i);
+            if f >= 2 && f >= frequency(i + 1) {
+                let pair = (seq[i], seq[i+1]);
+                let id = *rule_id.entry(pair).or_insert_with(|| {
+                    rules.push((pair.0 as usize, pair.1 as usize));
+                    u32::try_from(NTERM + rules.len() - 1).expect("Text has too many rules, build its grammar with bigrepair.")
+                });
+                next.push(id);
+                i += 2;
+            } else {
+                next.push(seq[i]);
+                i += 1;
+            }
+        }
+        seq = next;
+    }
+
+    fold(seq.into_iter().map(|x| x as usize).collect(), &mut rules);
+    return rules;
+}
+
+/// Adds rules deriving the sequence `seq` by pairing adjacent symbols, until a single symbol
+/// is left. Returns that symbol, it is the last rule whenever `seq` has at least two symbols.
+pub fn fold(mut seq: Vec<usize>, rules: &mut Vec<(usize, usize)>) -> usize {
+    assert!(!seq.is_empty(), "Cannot fold an empty sequence.");
+    while seq.len() > 1 {
+        let mut next = Vec::with_capacity(seq.len() / 2 + 1);
+        for pair in seq.chunks(2) {
+            if pair.len() == 2 {
+                rules.push((pair[0], pair[1]));
+                next.push(NTERM + rules.len() - 1);
+            } else {
+                next.push(pair[0]);
+            }
+        }
+        seq = next;
+    }
+    return seq[0];
+}
+
+/// Counts adjacent pairs, overlapping occurrences in runs (e.g. `aaa`) are counted once.
+fn pair_frequencies(seq: &[u32]) -> HashMap<(u32, u32), usize> {
+    let mut freq = HashMap::new();
+    let mut i = 0;
+    while i + 1 < seq.len() {
+        *freq.entry((seq[i], seq[i+1])).or_insert(0) += 1;
+        let run = seq[i] == seq[i+1] && i + 2 < seq.len() && seq[i+2] == seq[i];
+        i += if run { 2 } else { 1 };
+    }
+    return freq;
+}
@@ -0,0 +1,97 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+
+use crate::grammar::Grammar;
+use crate::graph::Graph;
+use crate::tag::{read_header, read_tag_array};
+
+/// Writes statistics of the grammar, compared to the sequences of the graph if it is given.
+pub fn grammar_stats<W: Write>(slp: &Path, gfa: Option<&Path>, mut output: W) {
+    let grammar = Grammar::from_file(slp);
+    let n = grammar.len();
+    let rules = grammar.rules_count();
+    let (memory, mapped) = grammar.memory_usage();
+
+    writeln!(output, "grammar\t{}", slp.display()).expect("Error writing output");
+    writeln!(output, "rules\t{rules}").expect("Error writing output");
+    writeln!(output, "terminals\t{} ({})", grammar.alphabet().len(),
+        String::from_utf8_lossy(grammar.alphabet()).escape_debug()).expect("Error writing output");
+    writeln!(output, "length\t{n}").expect("Error writing output");
+    writeln!(output, "height\t{}", grammar.height()).expect("Error writing output");
+    // every rule consists of two symbols
+    writeln!(output, "compression ratio\t{:.2}", n as f64 / (2 * rules) as f64).expect("Error writing output");
+
+    if let Some(gfa) = gfa {
+        let graph = Graph::from_file(gfa);
+        let segments: usize = graph.segments().iter().map(|(_, sequence)| sequence.len()).sum();
+        writeln!(output, "GFA sequence\t{segments}").expect("Error writing output");
+        writeln!(output, "GFA compression ratio\t{:.2}", n as f64 / segments as f64).expect("Error writing output");
+        writeln!(output, "compression ratio to GFA\t{:.2}", segments as f64 / (2 * rules) as f64)
+            .expect("Error writing output");
+    }
+
+    writeln!(output, "memory\t{memory} B ({mapped} B memory-mapped)").expect("Error writing output");
+
+    write_histogram(&mut output, "rule size", grammar.rule_sizes().iter().copied());
+}
+
+/// Number of nodes with the most runs listed by `tag_stats`.
+const TOP_NODES: usize = 10;
+
+/// Writes statistics of the run-length compressed tag array:
+/// the number of runs compared to the length of the text, the lengths of runs,
+/// the number of distinct tags and the nodes whose tags form the most runs.
+/// Text length and run lengths are only stored in the binary format.
+pub fn tag_stats<W: Write>(tag: &Path, mut output: W) {
+    let (_, stag, run_lengths) = read_tag_array(tag);
+    let runs = stag.len() / 2;
+
+    writeln!(output, "tag array\t{}", tag.display()).expect("Error writing output");
+    match read_header(tag) {
+        Some(header) => {
+            let n = header.text_len;
+            writeln!(output, "length\t{n}").expect("Error writing output");
+            writeln!(output, "runs\t{runs}").expect("Error writing output");
+            writeln!(output, "length/runs\t{:.2}", n as f64 / runs.max(1) as f64).expect("Error writing output");
+        },
+        None => {
+            writeln!(output, "length\tunknown (text format)").expect("Error writing output");
+            writeln!(output, "runs\t{runs}").expect("Error writing output");
+        }
+    }
+
+    let tags: Vec<_> = stag.iter().step_by(2).collect();
+    let distinct: HashSet<_> = tags.iter().collect();
+    writeln!(output, "distinct tags\t{}", distinct.len()).expect("Error writing output");
+
+    let mut node_runs: HashMap<usize, usize> = HashMap::new();
+    for tag in &tags { *node_runs.entry(tag.id).or_insert(0) += 1; }
+    writeln!(output, "nodes\t{}", node_runs.len()).expect("Error writing output");
+
+    if let Some(run_lengths) = run_lengths {
+        write_histogram(&mut output, "run length", run_lengths.into_iter());
+    }
+    write_histogram(&mut output, "runs per node", node_runs.values().copied());
+
+    let mut nodes: Vec<_> = node_runs.into_iter().collect();
+    nodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (id, count) in nodes.into_iter().take(TOP_NODES) {
+        writeln!(output, "node {id}\t{count} runs").expect("Error writing output");
+    }
+}
+
+/// Writes the number of values in every power-of-two bucket [2^i, 2^(i+1)).
+fn write_histogram<W: Write, I: Iterator<Item = usize>>(output: &mut W, name: &str, values: I) {
+    let mut histogram: Vec<usize> = Vec::new();
+    for value in values.filter(|&value| value > 0) {
+        let bucket = (usize::BITS - 1 - value.leading_zeros()) as usize;
+        if histogram.len() <= bucket { histogram.resize(bucket + 1, 0); }
+        histogram[bucket] += 1;
+    }
+    for (bucket, &count) in histogram.iter().enumerate() {
+        if count == 0 { continue; }
+        writeln!(output, "{name} [{}, {})\t{count}", 1usize << bucket, 1usize << (bucket + 1))
+            .expect("Error writing output");
+    }
+}
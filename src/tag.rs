@@ -0,0 +1,217 @@
+use std::fs::{read_to_string, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::array::read_u64;
+use crate::bits::{width, BitReader, BitWriter};
+use crate::gp::{Direction, GraphPos};
+
+/// Magic bytes at the start of a binary tag array file.
+const MAGIC: &[u8; 8] = b"MARIATAG";
+const VERSION: u64 = 1;
+/// magic, version, text length, graph checksum, number of runs and 4 field widths
+const HEADER_LEN: usize = 9 * 8;
+
+/// Information stored in the header of a binary tag array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagHeader {
+    /// length of the text, i.e. total length of the paths
+    pub text_len: usize,
+    /// checksum of the GFA file the tag array was built from
+    pub graph_checksum: u64,
+    pub runs: usize,
+}
+
+/// Run of equal tags in the tag array, sampled at its first and last suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    pub start: usize,
+    pub end: usize,
+    pub tag: GraphPos,
+    pub len: usize,
+}
+
+enum Format {
+    /// text lines `<sa>\t<id><sign>:<pos>` at the start and end of every run
+    Text,
+    /// bit-packed runs with the widths of suffixes, ids, positions and lengths
+    Binary { bits: BitWriter, widths: [usize; 4] },
+}
+
+/// Writes runs of the tag array to the file as they are sampled, so the runs are never kept in memory.
+pub struct TagWriter {
+    writer: BufWriter<File>,
+    format: Format,
+    runs: usize,
+}
+
+impl TagWriter {
+    /// Creates a tag array in the text format.
+    pub fn text(tag: &Path) -> Self {
+        return TagWriter { writer: create(tag), format: Format::Text, runs: 0 };
+    }
+
+    /// Creates a tag array in the binary format.
+    /// Every run is stored as the suffixes at its start and end, its tag and its length,
+    /// each field bit-packed with the width needed for the largest possible value,
+    /// i.e. the length of the text, the largest node id and the largest position in a node.
+    pub fn binary(tag: &Path, text_len: usize, graph_checksum: u64, max_id: usize, max_pos: usize) -> Self {
+        let widths = [
+            width(text_len.saturating_sub(1) as u64),
+            width(max_id as u64),
+            width(max_pos as u64),
+            width(text_len as u64),
+        ];
+        let mut writer = create(tag);
+        // the number of runs is written by finish
+        let header = [VERSION, text_len as u64, graph_checksum, 0];
+        writer.write_all(MAGIC).expect("Error writing tags.");
+        for x in header.into_iter().chain(widths.map(|w| w as u64)) {
+            writer.write_all(&x.to_le_bytes()).expect("Error writing tags.");
+        }
+        return TagWriter { writer, format: Format::Binary { bits: BitWriter::default(), widths }, runs: 0 };
+    }
+
+    pub fn push(&mut self, run: Run) {
+        self.runs += 1;
+        match &mut self.format {
+            Format::Text => {
+                writeln!(self.writer, "{}\t{}", run.start, run.tag).expect("Error while writing tags.");
+                writeln!(self.writer, "{}\t{}", run.end, run.tag).expect("Error while writing tags.");
+            },
+            Format::Binary { bits, widths } => {
+                bits.push(run.start as u64, widths[0]);
+                bits.push(run.end as u64, widths[0]);
+                bits.push(run.tag.id as u64, widths[1]);
+                bits.push((run.tag.sign == Direction::RevComp) as u64, 1);
+                bits.push(run.tag.pos as u64, widths[2]);
+                bits.push(run.len as u64, widths[3]);
+                for word in bits.take_full_words() {
+                    self.writer.write_all(&word.to_le_bytes()).expect("Error writing tags.");
+                }
+            },
+        }
+    }
+
+    /// Writes the remaining bits and the number of runs.
+    pub fn finish(self) {
+        let mut writer = self.writer;
+        if let Format::Binary { bits, .. } = self.format {
+            for word in bits.into_words() {
+                writer.write_all(&word.to_le_bytes()).expect("Error writing tags.");
+            }
+            writer.seek(SeekFrom::Start(4 * 8)).expect("Error writing tags.");
+            writer.write_all(&(self.runs as u64).to_le_bytes()).expect("Error writing tags.");
+        }
+        writer.flush().expect("Error writing.");
+    }
+}
+
+fn create(tag: &Path) -> BufWriter<File> {
+    return BufWriter::new(File::create(tag).unwrap_or_else(|_| panic!("Cannot open file {}", tag.display())));
+}
+
+fn is_binary(tag: &Path) -> bool {
+    let mut file = File::open(tag).unwrap_or_else(|_| panic!("Cannot open file {}", tag.display()));
+    let mut magic = [0; 8];
+    return file.read_exact(&mut magic).is_ok() && &magic == MAGIC;
+}
+
+/// Returns the header of a binary tag array, None for a tag array in the text format.
+pub fn read_header(tag: &Path) -> Option<TagHeader> {
+    if !is_binary(tag) { return None; }
+    let mut buffer = vec![0; HEADER_LEN];
+    File::open(tag).and_then(|mut f| f.read_exact(&mut buffer)).expect("Cannot read tag array header.");
+    return Some(parse_header(&buffer));
+}
+
+/// Checks that a binary tag array was built from the graph with the checksum
+/// whose paths have total length `text_len`.
+/// Tag arrays in the text format carry no header and are always accepted.
+pub fn check_header(tag: &Path, graph_checksum: u64, text_len: usize) -> Result<(), String> {
+    let Some(header) = read_header(tag) else { return Ok(()) };
+    if header.text_len != text_len {
+        return Err(format!("Tag array {} was built for a text of length {}, but the paths have total length {}.",
+            tag.display(), header.text_len, text_len));
+    }
+    if header.graph_checksum != graph_checksum {
+        return Err(format!("Tag array {} was built from a different graph.", tag.display()));
+    }
+    return Ok(());
+}
+
+fn parse_header(buffer: &[u8]) -> TagHeader {
+    let version = read_u64(buffer, 1);
+    assert_eq!(version, VERSION, "Unsupported version of binary tag array, recreate it with maria index.");
+    TagHeader {
+        text_len: read_u64(buffer, 2) as usize,
+        graph_checksum: read_u64(buffer, 3),
+        runs: read_u64(buffer, 4) as usize,
+    }
+}
+
+/// Reads sampled suffix array, sampled tag array and lengths of runs.
+/// Run lengths are not available for the text format.
+pub fn read_tag_array(tag: &Path) -> (Vec<usize>, Vec<GraphPos>, Option<Vec<usize>>) {
+    if !is_binary(tag) {
+        let (ssa, stag) = read_text(tag);
+        return (ssa, stag, None);
+    }
+
+    let buffer = std::fs::read(tag).unwrap_or_else(|_| panic!("Cannot read file {}", tag.display()));
+    assert!(buffer.len() >= HEADER_LEN, "Tag array file is truncated.");
+    let header = parse_header(&buffer);
+    let widths: Vec<usize> = (5..9).map(|i| read_u64(&buffer, i) as usize).collect();
+    let words: Vec<u64> = buffer[HEADER_LEN..].chunks_exact(8)
+        .map(|x| u64::from_le_bytes(x.try_into().unwrap())).collect();
+    let run_bits = 2 * widths[0] + widths[1] + 1 + widths[2] + widths[3];
+    assert!(words.len() * 64 >= header.runs * run_bits, "Tag array file is truncated.");
+
+    let mut bits = BitReader::new(&words);
+    let mut ssa = Vec::with_capacity(2 * header.runs);
+    let mut stag = Vec::with_capacity(2 * header.runs);
+    let mut run_lengths = Vec::with_capacity(header.runs);
+    for _ in 0..header.runs {
+        ssa.push(bits.read(widths[0]) as usize);
+        ssa.push(bits.read(widths[0]) as usize);
+        let id = bits.read(widths[1]) as usize;
+        let sign = if bits.read(1) == 1 { Direction::RevComp } else { Direction::Forward };
+        let pos = bits.read(widths[2]) as usize;
+        stag.push(GraphPos{id, sign, pos});
+        stag.push(GraphPos{id, sign, pos});
+        run_lengths.push(bits.read(widths[3]) as usize);
+    }
+    return (ssa, stag, Some(run_lengths));
+}
+
+fn read_text(tag: &Path) -> (Vec<usize>, Vec<GraphPos>) {
+    let res: (Vec<_>, Vec<_>) = read_to_string(tag).unwrap().lines().map(|line| {
+        let z: Vec<_> = line.split('\t').collect();
+        if z.len() != 2 { panic!("Tag array line {line} is incorrect.") }
+        let sa_value: usize = z[0].parse().unwrap();
+        let tag_value: GraphPos = z[1].parse().unwrap();
+        (sa_value, tag_value)
+    }).unzip();
+    return res;
+}
+
+#[test]
+fn binary_tag_array_is_read_back() {
+    let dir = crate::tests::TempDir::new("binary_tag");
+    let filename = dir.join("graph.tag");
+    let gp = |s: &str| s.parse::<GraphPos>().unwrap();
+    let runs = [
+        Run{start: 7, end: 7, tag: gp("2+:1"), len: 1},
+        Run{start: 3, end: 12, tag: gp("10-:4"), len: 4},
+        Run{start: 0, end: 5, tag: gp("0+:0"), len: 2},
+    ];
+    let mut writer = TagWriter::binary(&filename, 13, 42, 10, 4);
+    for run in runs { writer.push(run); }
+    writer.finish();
+
+    assert_eq!(read_header(&filename), Some(TagHeader{text_len: 13, graph_checksum: 42, runs: 3}));
+    let ssa = runs.iter().flat_map(|run| [run.start, run.end]).collect();
+    let stag = runs.iter().flat_map(|run| [run.tag, run.tag]).collect();
+    let run_lengths = runs.iter().map(|run| run.len).collect();
+    assert_eq!(read_tag_array(&filename), (ssa, stag, Some(run_lengths)));
+}
@@ -0,0 +1,78 @@
+use crate::check::{check, check_index};
+use crate::checksum::file_checksum;
+use crate::index::{index_path, Index, IndexOptions};
+use crate::grammar::Grammar;
+use crate::repair::repair;
+use crate::graph::Graph;
+use crate::{create_grammar, create_tag};
+use crate::tests::{temp_index, TempGfa};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn check_files(gfa: &Path, tag: &Path, slp: &Path) -> Result<(), String> {
+    return check(&Graph::from_file(gfa), file_checksum(gfa), tag, slp);
+}
+
+#[test]
+fn check_detects_inconsistent_index() {
+    let gfa = TempGfa::new("check", "data/real/SARS-CoV2.5.gfa");
+    let tag = gfa.with_extension("tag");
+    let slp = gfa.with_extension("slp");
+    create_grammar(&Graph::from_file(&gfa), &slp, false);
+    let triggers = PathBuf::from("data/pftag/triggers.txt");
+    create_tag(&Graph::from_file(&gfa), file_checksum(&gfa), &triggers, &tag, &IndexOptions::default());
+    assert_eq!(check_files(&gfa, &tag, &slp), Ok(()));
+    assert!(!tag.with_extension("paths.gfa").exists());
+
+    // binary tag array of a modified graph
+    let correct_gfa = fs::read(&gfa).unwrap();
+    fs::write(&gfa, [&correct_gfa[..], b"H\tVN:Z:1.0\n"].concat()).unwrap();
+    let message = check_files(&gfa, &tag, &slp).unwrap_err();
+    assert!(message.contains("was built from a different graph"), "{message}");
+    fs::write(&gfa, correct_gfa).unwrap();
+
+    create_tag(&Graph::from_file(&gfa), file_checksum(&gfa), &triggers, &tag, &IndexOptions{text: true, ..Default::default()});
+    assert_eq!(check_files(&gfa, &tag, &slp), Ok(()));
+
+    // tag array with a shifted position
+    let correct_tag = fs::read_to_string(&tag).unwrap();
+    let (first, rest) = correct_tag.split_once('\n').unwrap();
+    let (sa, gp) = first.split_once('\t').unwrap();
+    let shifted: usize = sa.parse::<usize>().unwrap() + 1;
+    fs::write(&tag, format!("{shifted}\t{gp}\n{rest}")).unwrap();
+    let message = check_files(&gfa, &tag, &slp).unwrap_err();
+    assert!(message.starts_with("Tag array entry 0"), "{message}");
+    fs::write(&tag, correct_tag).unwrap();
+
+    // grammar of a text with a single substitution
+    let mut text = Graph::from_file(&gfa).text();
+    text[1000] = if text[1000] == b'A' { b'C' } else { b'A' };
+    Grammar::from_rules(&repair(&text)).write_binary(&slp);
+    let message = check_files(&gfa, &tag, &slp).unwrap_err();
+    assert!(message.starts_with("Grammar differs from the graph at text position 1000"), "{message}");
+
+    // grammar of another graph
+    create_grammar(&Graph::from_file("data/pftag/test.no_overlap.gfa"), &slp, false);
+    let message = check_files(&gfa, &tag, &slp).unwrap_err();
+    assert!(message.starts_with("Grammar generates a text of length"), "{message}");
+}
+
+#[test]
+fn check_detects_modified_index() {
+    let (gfa, index) = temp_index("check_index", "data/real/SARS-CoV2.5.gfa", Some(Path::new("data/pftag/triggers.txt")), &IndexOptions::default());
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+
+    // index of another graph
+    let message = check_index(&PathBuf::from("data/pftag/test.no_overlap.gfa"), &index_path(&gfa)).unwrap_err();
+    assert!(message.starts_with("Index"), "{message}");
+    assert!(message.contains("was built from a different graph"), "{message}");
+
+    // component replaced by a component of another index
+    create_grammar(&Graph::from_file("data/pftag/test.no_overlap.gfa"), &index.grammar(), false);
+    let message = Index::open(&index_path(&gfa)).unwrap_err();
+    assert!(message.ends_with("does not match the manifest, it was modified or belongs to another index."), "{message}");
+
+    // missing manifest
+    let message = Index::open(gfa.dir()).unwrap_err();
+    assert!(message.contains("is not an index"), "{message}");
+}
@@ -1,4 +1,5 @@
 use crate::grammar::Grammar;
+use crate::tests::TempDir;
 use std::{process::Command, fs::File};
 use std::str;
 use std::io::Write;
@@ -8,10 +9,9 @@ use proptest::prelude::*;
 fn test() {
     let grammar = Grammar::from_file("data/pftag/test_join.txt.plainslp");
 
-    for i in 0..grammar.len() {
-        print!("{}", grammar[i] as char);
-    }
-    println!();
+    let text = grammar.extract(0, grammar.len());
+    println!("{}", str::from_utf8(&text).unwrap());
+    assert_eq!(text, std::fs::read("data/pftag/test_join.txt").unwrap());
 }
 
 #[test]
@@ -28,6 +28,71 @@ fn test_my_grammar() {
     g.print(output);
 }
 
+#[test]
+fn binary_grammar_is_equal_to_plain_grammar() {
+    let plain = Grammar::from_file("data/pftag/test_join.txt.plainslp");
+    let dir = TempDir::new("binary_grammar");
+    let filename = dir.join("test_join.slp");
+    plain.write_binary(&filename);
+    let binary = Grammar::from_file(&filename);
+
+    assert_eq!(binary.len(), plain.len());
+    for i in 0..plain.len() {
+        assert_eq!(binary[i], plain[i]);
+    }
+    for i in 0..plain.len() {
+        assert_eq!(binary.lce(0, i), plain.lce(0, i));
+    }
+    // rules and their fingerprints are mapped, only the terminals are copied
+    let (memory, mapped) = binary.memory_usage();
+    assert_eq!(memory - mapped, binary.alphabet().len());
+}
+
+fn naive_lce(g: &Grammar, i: usize, j: usize) -> usize {
+    let mut l = 0;
+    while i+l < g.len() && j+l < g.len() && g[i+l] == g[j+l] { l += 1; }
+    return l;
+}
+
+#[test]
+fn lce_is_equal_to_naive_lce() {
+    let g = Grammar::from_file("data/real/SARS-CoV2.5.fnajoin.plainslp");
+    let n = g.len();
+    let mut x: usize = 42;
+    for _ in 0..200 {
+        x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let i = (x >> 33) % n;
+        // the genomes are ~29800 bp long, so shifted positions share long prefixes
+        let j = (i + 29800 + (x >> 20) % 100) % n;
+        assert_eq!(g.lce(i, j), naive_lce(&g, i, j), "i = {i}, j = {j}");
+        assert_eq!(g.lce(j, i), naive_lce(&g, j, i), "i = {j}, j = {i}");
+    }
+}
+
+#[test]
+fn repair_files_are_loaded() {
+    let dir = TempDir::new("repair_files");
+    let prefix = dir.join("grammar");
+    let write_u32s = |suffix: &str, values: &[u32]| {
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        std::fs::write(format!("{}{suffix}", prefix.display()), bytes).unwrap();
+    };
+    // 256 -> AC, 257 -> 256 G
+    write_u32s(".R", &[256, 65, 67, 256, 71]);
+    write_u32s(".C", &[256, 257, 84]);
+
+    let g = Grammar::from_repair_files(&prefix);
+    assert_eq!(g.extract(0, g.len()), b"ACACGT");
+
+    // RePair maps its 4 terminals to chars, 4 -> AC, 5 -> 4 G
+    let rules: Vec<u8> = [&4u32.to_le_bytes()[..], b"ACGT"].concat().into_iter()
+        .chain([0u32, 1, 4, 2].iter().flat_map(|x| x.to_le_bytes())).collect();
+    std::fs::write(format!("{}.R", prefix.display()), rules).unwrap();
+    write_u32s(".C", &[4, 5, 3]);
+    let g = Grammar::from_repair_files(&prefix);
+    assert_eq!(g.extract(0, g.len()), b"ACACGT");
+}
+
 fn test_grammar_for_string(s: &[u8]) {
     {
         let mut out = File::create("data/temporary/seq.txt").expect("Cannot create file.");
@@ -40,14 +105,8 @@ fn test_grammar_for_string(s: &[u8]) {
         .output()
         .expect("Failed to run bigrepair");
 
-    println!("Converting rules to plaintext...");
-    Command::new("./scripts/print_plain_slp")
-        .args(["data/temporary/seq.txt"])
-        .output()
-        .expect("Failed to convert to plain text");
-
     println!("Loading grammar...");
-    let g = Grammar::from_file("data/temporary/seq.txt.plainslp");
+    let g = Grammar::from_repair_files("data/temporary/seq.txt");
 
     println!("Testing lengths...");
     assert_eq!(g.len(), s.len());
@@ -64,7 +123,55 @@ fn complex_test() {
     test_grammar_for_string(&s);
 }
 
+#[test]
+fn extract_is_equal_to_indexing() {
+    let g = Grammar::from_file("data/pftag/test_join.txt.plainslp");
+    for start in 0..=g.len() {
+        for len in 0..=(g.len() - start + 1) {
+            let expected: Vec<_> = (start..g.len().min(start + len)).map(|i| g[i]).collect();
+            assert_eq!(g.extract(start, len), expected);
+        }
+    }
+}
+
+#[test]
+fn balancing_reduces_height() {
+    // left-deep caterpillar (((AC)G)T)... of height 999
+    let mut rules = vec![(65, 67)];
+    for i in 1..999 { rules.push((255 + i, b"ACGT"[i % 4] as usize)); }
+    let g = Grammar::from_rules(&rules);
+    assert_eq!(g.height(), 999);
+
+    let balanced = g.balance();
+    assert!(balanced.height() <= 2 * 10 + 2, "height {}", balanced.height());
+    assert_eq!(balanced.extract(0, balanced.len()), g.extract(0, g.len()));
+}
+
 proptest! {
+    #[test]
+    fn balanced_grammar_generates_the_same_text(s in "[ACGT]{2,200}") {
+        let g = Grammar::from_rules(&crate::repair::repair(s.as_bytes())).balance();
+        prop_assert_eq!(g.extract(0, g.len()), s.as_bytes());
+    }
+
+    #[test]
+    fn extract_returns_substring(s in "[ACGT]{2,100}", start in 0..100usize, len in 0..100usize) {
+        let g = Grammar::from_rules(&crate::repair::repair(s.as_bytes()));
+        let start = start % s.len();
+        let end = s.len().min(start + len);
+        prop_assert_eq!(g.extract(start, len), &s.as_bytes()[start..end]);
+    }
+
+    #[test]
+    fn lce_is_equal_to_naive_lce_on_random_text(s in "[AC]{2,60}") {
+        let g = Grammar::from_rules(&crate::repair::repair(s.as_bytes()));
+        for i in 0..g.len() {
+            for j in 0..g.len() {
+                prop_assert_eq!(g.lce(i, j), naive_lce(&g, i, j));
+            }
+        }
+    }
+
     #[test]
     #[ignore]
     fn proptesting(s in "[ACGT][ACGT]+") {
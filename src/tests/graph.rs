@@ -0,0 +1,116 @@
+use crate::check::check_index;
+use crate::graph::{Graph, Walk};
+use crate::index::{index_path, Index, IndexOptions};
+use crate::layout::Layout;
+use crate::tag::read_tag_array;
+use crate::{extract_path, path_coordinates, GraphPos};
+use crate::tests::{temp_index, TempDir, TempGfa};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn walks_are_read_as_paths() {
+    let (path_starts, path_names, node_starts, node_names) = path_coordinates(&Graph::from_file("data/test_walks.gfa"));
+    let (expected_starts, _, expected_node_starts, expected_node_names) = path_coordinates(&Graph::from_file("data/test_small_gfa.gfa"));
+    assert_eq!(path_starts, expected_starts);
+    assert_eq!(path_names, vec!["x#1#chr1:0-10", "y#2#chr1"]);
+    assert_eq!(node_starts, expected_node_starts);
+    assert_eq!(node_names, expected_node_names);
+
+    let graph = Graph::from_file("data/test_walks.gfa");
+    let walk = Walk { sample: "x".to_string(), haplotype: 1, contig: "chr1".to_string(), interval: Some((0, 10)) };
+    assert_eq!(graph.paths[0].walk, Some(walk));
+    assert_eq!(graph.text(), Graph::from_file("data/test_small_gfa.gfa").text());
+}
+
+#[test]
+fn index_of_walks_and_named_segments_equals_index_of_paths() {
+    let triggers = Path::new("data/triggers.txt");
+    let mut tags = Vec::new();
+    let graphs = [("walks", "data/test_walks.gfa"), ("named", "data/test_named.gfa"), ("paths", "data/test_small_gfa.gfa")];
+    for (name, source) in graphs {
+        let (_gfa, index) = temp_index(&format!("graph_{name}"), source, Some(triggers), &IndexOptions::default());
+        tags.push(read_tag_array(&index.tag()));
+    }
+    assert_eq!(tags[0], tags[2]);
+    assert_eq!(tags[1], tags[2]);
+}
+
+#[test]
+fn gfa2_groups_are_read_as_paths() {
+    let (path_starts, path_names, node_starts, node_names) = path_coordinates(&Graph::from_file("data/test_gfa2.gfa"));
+    let (_, _, expected_node_starts, expected_node_names) = path_coordinates(&Graph::from_file("data/test_small_gfa.gfa"));
+    assert_eq!(path_starts, vec![0, 10, 22]);
+    assert_eq!(path_names, vec!["x", "y", "z"]);
+    assert_eq!(node_starts[..12], expected_node_starts[..]);
+    assert_eq!(node_names[..11], expected_node_names[..]);
+    // unordered group, edges are skipped, the group w of only edges is skipped
+    assert_eq!(node_names[11..], [GraphPos{id: 3, ..Default::default()}, GraphPos{id: 4, ..Default::default()}]);
+    assert_eq!(Graph::from_file("data/test_gfa2.gfa").text(), b"AGGTTAGTACAGTAACGTTAACGTAC");
+}
+
+#[test]
+fn segment_names_are_mapped_to_ids() {
+    let (_, _, node_starts, node_names) = path_coordinates(&Graph::from_file("data/test_named.gfa"));
+    let (_, _, expected_node_starts, expected_node_names) = path_coordinates(&Graph::from_file("data/test_small_gfa.gfa"));
+    assert_eq!(node_starts, expected_node_starts);
+    assert_eq!(node_names, expected_node_names);
+
+    let layout = Layout::new(&Graph::from_file("data/test_named.gfa"));
+    assert_eq!(layout.segment_names, vec!["seg0", "seg1", "seg2", "seg3", "seg4"]);
+    let (path, ..) = extract_path(12, 4, &layout.node_starts, &layout.node_names, &layout.node_overlaps, &layout.segment_names);
+    assert_eq!(path, "<seg2<seg1");
+    assert!(Layout::new(&Graph::from_file("data/test_small_gfa.gfa")).segment_names.is_empty());
+
+    let dir = TempDir::new("named_layout");
+    let filename = dir.join("graph.layout");
+    layout.write(&filename);
+    assert_eq!(Layout::read(&filename), layout);
+}
+
+#[test]
+#[should_panic(expected = "Segment seg1 is defined more than once.")]
+fn duplicate_segments_are_refused() {
+    let gfa = TempGfa::new("graph_duplicate", "data/test_named.gfa");
+    fs::write(&gfa, [fs::read(&gfa).unwrap(), b"S\tseg1\tACGT\n".to_vec()].concat()).unwrap();
+    Graph::from_file(&gfa);
+}
+
+#[test]
+#[should_panic(expected = "Segments 1 and 01 have the same id 1.")]
+fn colliding_segment_ids_are_refused() {
+    let gfa = TempGfa::new("graph_colliding", "data/test_small_gfa.gfa");
+    fs::write(&gfa, [fs::read(&gfa).unwrap(), b"S\t01\tACGT\n".to_vec()].concat()).unwrap();
+    Graph::from_file(&gfa);
+}
+
+#[test]
+fn overlaps_are_not_repeated_in_the_text() {
+    let graph = Graph::from_file("data/pftag/test.gfa");
+    assert_eq!(graph.text(), b"GTACGTGTACGTGTGTA.GTACGTCTGTGTTACGT.");
+    let (_, _, node_starts, node_names) = path_coordinates(&graph);
+    assert_eq!(node_starts[..4], [0, 2, 6, 8]);
+    assert_eq!(node_names[1], "3+".parse().unwrap());
+    assert_eq!(graph.overlaps()[..4], [0, 1, 1, 1]);
+
+    // >3>5>3 from the middle of the first 3+ to the middle of the second one
+    let segment_names = Vec::new();
+    let (path, plen, pstart, pend) = extract_path(4, 6, &node_starts, &node_names, &graph.overlaps(), &segment_names);
+    assert_eq!((path.as_str(), plen, pstart, pend), (">3>5>3", 5 + 2 + 4, 3, 9));
+
+    let dir = TempDir::new("overlaps");
+    let filename = dir.join("graph.gfa");
+    fs::write(&filename, "S\t1\tACGT\nS\t2\tGTTA\nP\tp\t1+,2+,1-\t2M,0M\n").unwrap();
+    assert_eq!(Graph::from_file(&filename).text(), b"ACGTTAACGT");
+}
+
+#[test]
+fn index_of_overlapping_segments_is_consistent() {
+    let triggers = Path::new("data/pftag/triggers.txt");
+    let (gfa, index) = temp_index("graph_overlaps", "data/pftag/test.gfa", Some(triggers), &IndexOptions::default());
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+
+    let tag = read_tag_array(&index.tag());
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &IndexOptions{force: true, naive: true, ..Default::default()});
+    assert_eq!(read_tag_array(&index.tag()), tag);
+}
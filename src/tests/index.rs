@@ -0,0 +1,174 @@
+use crate::check::check_index;
+use crate::grammar::Grammar;
+use crate::graph::Graph;
+use crate::index::{index_path, Index, IndexOptions};
+use crate::layout::Layout;
+use crate::repair::repair;
+use crate::tag::read_header;
+use crate::tests::{temp_index, TempGfa};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::SystemTime;
+
+fn modified(path: &Path) -> SystemTime {
+    return fs::metadata(path).unwrap().modified().unwrap();
+}
+
+#[test]
+fn index_contains_path_sequences() {
+    let (gfa, index) = temp_index("index_text", "data/pftag/test.no_overlap.gfa", Some(Path::new("data/pftag/triggers.txt")), &IndexOptions::default());
+
+    let fna = fs::read_to_string(index.text()).unwrap();
+    let records: Vec<_> = fna.lines().collect();
+    assert_eq!(records[0], ">0");
+    assert_eq!(records[2], ">1");
+    let sequences: String = records.iter().filter(|line| !line.starts_with('>')).copied().collect();
+    assert_eq!(sequences.as_bytes(), Graph::from_file(&gfa).text());
+}
+
+#[test]
+fn index_skips_components_that_are_up_to_date() {
+    let gfa = TempGfa::new("index_skip", "data/pftag/test.no_overlap.gfa");
+    let triggers = gfa.dir().join("triggers.txt");
+    fs::copy("data/pftag/triggers.txt", &triggers).unwrap();
+    let index = Index::create(&gfa, Some(&triggers), &index_path(&gfa), &IndexOptions{force: true, ..Default::default()});
+    let grammar = modified(&index.grammar());
+    let tag = modified(&index.tag());
+    let grammar_content = fs::read(index.grammar()).unwrap();
+
+    // nothing changed
+    Index::create(&gfa, Some(&triggers), &index_path(&gfa), &IndexOptions::default());
+    assert_eq!(modified(&index.grammar()), grammar);
+    assert_eq!(modified(&index.tag()), tag);
+
+    // only the tag array depends on triggers and format
+    fs::copy("data/triggers.txt", &triggers).unwrap();
+    Index::create(&gfa, Some(&triggers), &index_path(&gfa), &IndexOptions::default());
+    assert_eq!(modified(&index.grammar()), grammar);
+    assert_eq!(fs::read(index.triggers()).unwrap(), fs::read(&triggers).unwrap());
+
+    let text = IndexOptions{text: true, ..Default::default()};
+    Index::create(&gfa, Some(&triggers), &index_path(&gfa), &text);
+    assert_eq!(read_header(&index.tag()), None);
+    assert!(Index::open(&index_path(&gfa)).is_ok());
+
+    // modified component is rebuilt
+    fs::write(index.grammar(), b"").unwrap();
+    Index::create(&gfa, Some(&triggers), &index_path(&gfa), &text);
+    assert_eq!(fs::read(index.grammar()).unwrap(), grammar_content);
+    assert!(Index::open(&index_path(&gfa)).is_ok());
+}
+
+#[test]
+fn index_selects_triggers() {
+    let options = IndexOptions{phrase_len: 100, ..Default::default()};
+    let (gfa, index) = temp_index("index_auto", "data/real/SARS-CoV2.5.gfa", None, &options);
+
+    let triggers = fs::read_to_string(index.triggers()).unwrap();
+    assert!(!triggers.is_empty());
+    assert!(triggers.lines().all(|trigger| trigger.len() == 5), "{triggers}");
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+
+    // selection is deterministic, so the tag array is up to date
+    let tag = modified(&index.tag());
+    Index::create(&gfa, None, &index_path(&gfa), &options);
+    assert_eq!(modified(&index.tag()), tag);
+}
+
+#[test]
+fn index_contains_both_strands() {
+    let triggers = Path::new("data/pftag/triggers.txt");
+    let (gfa, index) = temp_index("index_revcomp", "data/pftag/test.gfa", Some(triggers), &IndexOptions{revcomp: true, ..Default::default()});
+
+    let fna = fs::read_to_string(index.text()).unwrap();
+    let records: Vec<_> = fna.lines().collect();
+    assert_eq!(records.iter().filter(|line| line.starts_with('>')).copied().collect::<Vec<_>>(), [">0", ">1", ">0_rev", ">1_rev"]);
+    // reverse complement of GTACGTGTACGTGTGTA.
+    assert_eq!(records[5], ".TACACACGTACACGTAC");
+    assert!(Layout::read(&index.layout()).reverse_paths);
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+
+    // the index of one strand is rebuilt
+    let grammar = fs::read(index.grammar()).unwrap();
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &IndexOptions::default());
+    assert_ne!(fs::read(index.grammar()).unwrap(), grammar);
+    assert!(!Layout::read(&index.layout()).reverse_paths);
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+}
+
+#[test]
+fn index_imports_grammar() {
+    let triggers = Path::new("data/pftag/triggers.txt");
+    let (gfa, index) = temp_index("index_grammar", "data/real/SARS-CoV2.5.gfa", Some(triggers), &IndexOptions::default());
+    let grammar = gfa.dir().join("imported.slp");
+    Grammar::from_rules(&repair(&Graph::from_file(&gfa).text())).balance().write_binary(&grammar);
+
+    let options = IndexOptions{force: true, grammar: Some(grammar.clone()), ..Default::default()};
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &options);
+    assert_eq!(fs::read(index.grammar()).unwrap(), fs::read(&grammar).unwrap());
+    assert_eq!(check_index(&gfa, &index_path(&gfa)), Ok(()));
+}
+
+#[test]
+#[should_panic(expected = "does not generate the path sequences")]
+fn index_refuses_grammar_of_another_graph() {
+    let (gfa, _index) = temp_index("index_wrong_grammar", "data/pftag/test.no_overlap.gfa", Some(Path::new("data/pftag/triggers.txt")), &IndexOptions::default());
+    let options = IndexOptions{force: true, grammar: Some("data/pftag/test_join.txt.plainslp".into()), ..Default::default()};
+    Index::create(&gfa, Some(Path::new("data/pftag/triggers.txt")), &index_path(&gfa), &options);
+}
+
+#[test]
+fn open_checksums_only_modified_components() {
+    let (gfa, index) = temp_index("index_open", "data/pftag/test.no_overlap.gfa", Some(Path::new("data/pftag/triggers.txt")), &IndexOptions::default());
+    let mut text = fs::read(index.text()).unwrap();
+    let modified = fs::metadata(index.text()).unwrap().modified().unwrap();
+
+    // touching a component does not change its checksum
+    fs::write(index.text(), &text).unwrap();
+    assert!(Index::open(&index_path(&gfa)).is_ok());
+
+    // a component of another size is refused without reading it
+    fs::write(index.text(), b">0\n").unwrap();
+    assert!(Index::open(&index_path(&gfa)).is_err());
+
+    // a component modified in place is refused by open, unless its modification time is kept
+    text[3] = b'N';
+    fs::write(index.text(), &text).unwrap();
+    let file = fs::File::options().write(true).open(index.text()).unwrap();
+    file.set_modified(modified + std::time::Duration::from_secs(1)).unwrap();
+    assert!(Index::open(&index_path(&gfa)).is_err());
+    file.set_modified(modified).unwrap();
+    let index = Index::open(&index_path(&gfa)).unwrap();
+    assert!(index.verify().is_err());
+}
+
+#[test]
+fn moni_index_is_kept_while_it_exists() {
+    let triggers = Path::new("data/pftag/triggers.txt");
+    let (gfa, index) = temp_index("index_moni", "data/pftag/test.no_overlap.gfa", Some(triggers), &IndexOptions::default());
+    // stands in for moni build, appends a line to the index on every run
+    let moni = gfa.dir().join("moni.sh");
+    fs::write(&moni, "#!/bin/sh\necho built >> \"$6.thrbv.ms\"\n").unwrap();
+    fs::set_permissions(&moni, fs::Permissions::from_mode(0o755)).unwrap();
+    let moni_index = index_path(&gfa).join("moni.thrbv.ms");
+    let with_moni = IndexOptions{moni: Some(moni), ..Default::default()};
+    let has_moni_entry = || fs::read_to_string(index_path(&gfa).join("manifest.tsv")).unwrap().contains("\nmoni\tmoni\t");
+
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &with_moni);
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &with_moni);
+    assert_eq!(fs::read_to_string(&moni_index).unwrap(), "built\n");
+
+    // kept without --moni
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &IndexOptions::default());
+    assert!(has_moni_entry());
+
+    // dropped if missing and rebuilt with --moni
+    fs::remove_file(&moni_index).unwrap();
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &IndexOptions::default());
+    assert!(!has_moni_entry());
+    Index::create(&gfa, Some(triggers), &index_path(&gfa), &with_moni);
+    assert_eq!(fs::read_to_string(&moni_index).unwrap(), "built\n");
+    assert!(has_moni_entry());
+    assert!(index.verify().is_ok());
+}
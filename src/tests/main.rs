@@ -1,4 +1,6 @@
 use gfa::gfa::GFA;
+use gfa::parser::GFAParser;
+use std::str;
 use crate::*;
 
 fn parse_graph(graph: &GFA<usize, ()>) -> (Vec<usize>, Vec<GraphPos>) {
@@ -46,7 +48,7 @@ fn process_graph_old(filename: &str) -> (Vec<usize>, Vec<String>, Vec<usize>, Ve
 #[test]
 fn test_process_graph() {
     let (ps1, pn1, ns1, nn1) = process_graph_old("data/real/SARS-CoV2.5.gfa");
-    let (ps2, pn2, ns2, nn2) = process_graph("data/real/SARS-CoV2.5.gfa");
+    let (ps2, pn2, ns2, nn2) = path_coordinates(&Graph::from_file("data/real/SARS-CoV2.5.gfa"));
 
     assert_eq!(ps1, ps2); // path_starts
     assert_eq!(pn1, pn2);
@@ -54,3 +56,101 @@ fn test_process_graph() {
     assert_eq!(nn1, nn2);
 }
 
+#[test]
+fn sampling_in_chunks_merges_runs_at_chunk_borders() {
+    let graph = Graph::from_file("data/test_small_gfa.gfa");
+    let (_, _, node_starts, node_names) = path_coordinates(&graph);
+    let node_overlaps = graph.overlaps();
+    let text = graph.text();
+    let sa = naive::suffix_array(&text);
+    let mut expected = Vec::new();
+    sample_runs(sa.iter().copied(), &node_starts, &node_names, &node_overlaps, |run| expected.push(run));
+
+    for threads in 2..5 {
+        for chunk_len in 1..sa.len() + 2 {
+            let mut runs = Vec::new();
+            sample_runs_in_chunks(sa.iter().copied(), &node_starts, &node_names, &node_overlaps, threads, chunk_len, |run| runs.push(run));
+            assert_eq!(runs, expected, "threads = {threads}, chunk_len = {chunk_len}");
+        }
+    }
+}
+
+#[test]
+fn multithreaded_sampled_arrays_are_equal() {
+    let gfa = "data/real/SARS-CoV2.5.gfa";
+    let triggers = "data/pftag/triggers.txt";
+    let graph = Graph::from_file(gfa);
+    let (_, _, node_starts, node_names) = path_coordinates(&graph);
+    let mut expected = Vec::new();
+    sample_tag_array(gfa, triggers, &node_starts, &node_names, &graph.overlaps(), 1, |run| expected.push(run));
+    let mut runs = Vec::new();
+    sample_tag_array(gfa, triggers, &node_starts, &node_names, &graph.overlaps(), 4, |run| runs.push(run));
+    assert_eq!(runs, expected);
+}
+
+#[test]
+fn reverse_matches_are_folded_to_the_forward_paths() {
+    let graph = Graph::from_file("data/pftag/test.gfa").with_reverse_paths();
+    let (path_starts, _, _, _) = path_coordinates(&graph);
+    let text = graph.text();
+    let reverse_start = path_starts[path_starts.len() / 2];
+    for sa in 0..text.len() {
+        for len in 1..5.min(text.len() - sa) {
+            let end = path_starts.iter().copied().find(|&start| start > sa).unwrap_or(text.len());
+            if sa + len > end { continue; }
+            let (forward, strand) = forward_strand(sa, len, &path_starts, text.len(), true);
+            if sa < reverse_start {
+                assert_eq!((forward, strand), (sa, '+'));
+            } else {
+                assert_eq!(strand, '-');
+                assert_eq!(reverse_complement(&text[sa..sa+len]), &text[forward..forward+len], "sa = {sa}, len = {len}");
+            }
+        }
+    }
+}
+
+#[test]
+fn paths_containing_a_walk_are_found_on_both_strands() {
+    let graph = Graph::from_file("data/pftag/test.gfa").with_reverse_paths();
+    let (path_starts, _, node_starts, node_names) = path_coordinates(&graph);
+    let path_steps = PathSteps::new(&path_starts, &node_starts, &node_names, true);
+    let walk = |nodes: &str| -> Vec<GraphPos> {
+        nodes.split(',').map(|x| x.parse().unwrap()).collect()
+    };
+
+    assert_eq!(path_steps.paths_containing(&walk("0+,3+")), vec![0, 1]);
+    assert_eq!(path_steps.paths_containing(&walk("3+,5+,3+")), vec![0]);
+    assert_eq!(path_steps.paths_containing(&walk("5+,5+")), vec![0, 1]);
+    assert_eq!(path_steps.paths_containing(&walk("4+")), vec![1]);
+    assert_eq!(path_steps.paths_containing(&walk("3-,0-")), vec![0, 1]);
+    assert_eq!(path_steps.paths_containing(&walk("1-,3-,6-")), vec![1]);
+    assert_eq!(path_steps.paths_containing(&walk("2+,0+")), Vec::<usize>::new());
+
+    // without reverse paths, walks are searched in both orientations
+    let graph = Graph::from_file("data/pftag/test.gfa");
+    let (path_starts, _, node_starts, node_names) = path_coordinates(&graph);
+    let path_steps = PathSteps::new(&path_starts, &node_starts, &node_names, false);
+    assert_eq!(path_steps.paths_containing(&walk("3+,5+,3+")), vec![0]);
+    assert_eq!(path_steps.paths_containing(&walk("3-,0-")), vec![0, 1]);
+    assert_eq!(path_steps.paths_containing(&walk("1-,3-,6-")), vec![1]);
+    assert_eq!(path_steps.paths_containing(&walk("2+,0+")), Vec::<usize>::new());
+}
+
+#[test]
+fn matches_are_clipped_at_the_end_of_their_path() {
+    let graph = Graph::from_file("data/pftag/test.gfa");
+    let (path_starts, _, node_starts, node_names) = path_coordinates(&graph);
+    let text_len = graph.text().len();
+    let end = path_starts[1];
+    assert_eq!(clip_to_path(end - 3, 10, &path_starts, text_len), 3);
+    assert_eq!(clip_to_path(end, 10, &path_starts, text_len), 10);
+    assert_eq!(clip_to_path(text_len - 2, 10, &path_starts, text_len), 2);
+
+    for sa in 0..text_len {
+        let len = clip_to_path(sa, text_len, &path_starts, text_len);
+        let (path, _, _, _) = extract_path(sa, len, &node_starts, &node_names, &graph.overlaps(), &[]);
+        let (first, last) = (node_starts.argpred(sa), node_starts.argpred(sa + len - 1));
+        assert_eq!(path.matches(['>', '<']).count(), last - first + 1, "sa = {sa}");
+        assert_eq!(path_starts.argpred(node_starts[last]), path_starts.argpred(sa), "sa = {sa}");
+    }
+}
@@ -1,4 +1,6 @@
-use crate::mem::MEMReader;
+use crate::mem::{LengthReader, MEMReader};
+use crate::tests::TempDir;
+use std::fs;
 
 #[test]
 fn mem_iterator() {
@@ -11,3 +13,31 @@ fn mem_iterator() {
         println!("{mems:?}");
     }
 }
+
+#[test]
+fn read_lengths_are_equal_in_fastq_and_lengths() {
+    let fastq: Vec<_> = LengthReader::reads("data/real/reads_R1.fastq").collect();
+    let lengths: Vec<_> = LengthReader::lengths("data/real/reads_R1.lengths").collect();
+    let ids: Vec<_> = MEMReader::new("data/real/reads_R1.mems", "data/real/reads_R1.pointers").map(|(id, _)| id).collect();
+
+    assert_eq!(fastq, lengths);
+    assert_eq!(fastq.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>(), ids);
+    assert_eq!(fastq[0], ("ENA|MW565758|MW565758.1_0_1/1".to_string(), 126));
+}
+
+#[test]
+fn read_lengths_of_multiline_records() {
+    let dir = TempDir::new("multiline_reads");
+    let (fasta, fastq, mems, ptrs) = (dir.join("reads.fa"), dir.join("reads.fq"), dir.join("reads.mems"), dir.join("reads.pointers"));
+    fs::write(&fasta, ">r1 first read\nACGT\nAC\n>r2\n\n>r3\nA\n").unwrap();
+    // the second quality starts with @ and spans two lines
+    fs::write(&fastq, "@r1 first read\nACGT\nAC\n+\nIIIIII\n@r2\n\n+\n\n@r3\nACG\n+r3\n@I\nI\n").unwrap();
+    fs::write(&mems, ">r1 first read\n(0,6) \n").unwrap();
+    fs::write(&ptrs, ">r1 first read\n0 0 0 0 0 0 \n").unwrap();
+
+    let expected = vec![("r1".to_string(), 6), ("r2".to_string(), 0), ("r3".to_string(), 1)];
+    assert_eq!(LengthReader::reads(&fasta).collect::<Vec<_>>(), expected);
+    let expected = vec![("r1".to_string(), 6), ("r2".to_string(), 0), ("r3".to_string(), 3)];
+    assert_eq!(LengthReader::reads(&fastq).collect::<Vec<_>>(), expected);
+    assert_eq!(MEMReader::new(&mems, &ptrs).map(|(id, _)| id).collect::<Vec<_>>(), ["r1"]);
+}
@@ -2,3 +2,70 @@ mod main;
 mod test_pfgraph;
 mod grammar;
 mod mem;
+mod repair;
+mod check;
+mod stats;
+mod index;
+mod naive;
+mod graph;
+
+use crate::index::{index_path, Index, IndexOptions};
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Temporary directory `maria_<name>_<pid>`, which is removed on drop, `name` has to be unique among the tests.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("maria_{name}_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        return TempDir(dir);
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+    fn deref(&self) -> &Path { &self.0 }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Copy of a GFA file `graph.gfa` in its own temporary directory, see `TempDir`.
+pub struct TempGfa {
+    dir: TempDir,
+    gfa: PathBuf,
+}
+
+impl TempGfa {
+    /// Copies `source` to the directory `maria_<name>_<pid>`.
+    pub fn new(name: &str, source: &str) -> Self {
+        let dir = TempDir::new(name);
+        let gfa = dir.join("graph.gfa");
+        fs::copy(source, &gfa).unwrap();
+        return TempGfa { dir, gfa };
+    }
+
+    pub fn dir(&self) -> &Path { &self.dir }
+}
+
+impl Deref for TempGfa {
+    type Target = Path;
+    fn deref(&self) -> &Path { &self.gfa }
+}
+
+impl AsRef<Path> for TempGfa {
+    fn as_ref(&self) -> &Path { &self.gfa }
+}
+
+/// Builds the index of a temporary copy of `source`, see `TempGfa::new`.
+pub fn temp_index(name: &str, source: &str, triggers: Option<&Path>, options: &IndexOptions) -> (TempGfa, Index) {
+    let gfa = TempGfa::new(name, source);
+    let index = Index::create(&gfa, triggers, &index_path(&gfa), options);
+    return (gfa, index);
+}
@@ -0,0 +1,69 @@
+use crate::gp::{Direction, GraphPos};
+use crate::graph::Graph;
+use crate::{naive, path_coordinates, sample_tag_array};
+use crate::tests::TempDir;
+use proptest::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn assert_same_sampled_arrays(gfa: &Path, triggers: &Path) {
+    let graph = Graph::from_file(gfa);
+    let (_, _, node_starts, node_names) = path_coordinates(&graph);
+    let mut expected = Vec::new();
+    sample_tag_array(gfa, triggers, &node_starts, &node_names, &graph.overlaps(), 1, |run| expected.push(run));
+    let mut runs = Vec::new();
+    naive::sample_tag_array(&graph, &node_starts, &node_names, &graph.overlaps(), 1, |run| runs.push(run));
+    assert_eq!(runs, expected);
+}
+
+#[test]
+fn naive_sampled_arrays_are_equal_to_pfg() {
+    assert_same_sampled_arrays(Path::new("data/test_small_gfa.gfa"), Path::new("data/pftag/triggers.txt"));
+    assert_same_sampled_arrays(Path::new("data/pftag/test.no_overlap.gfa"), Path::new("data/pftag/triggers.txt"));
+    assert_same_sampled_arrays(Path::new("data/real/SARS-CoV2.5.gfa"), Path::new("data/pftag/triggers.txt"));
+}
+
+/// Writes a graph with the segments and paths given as lists of oriented segment ids.
+fn write_graph(filename: &Path, segments: &[String], paths: &[Vec<GraphPos>]) {
+    let mut gfa = String::from("H\tVN:Z:1.1\n");
+    for (id, seq) in segments.iter().enumerate() {
+        gfa.push_str(&format!("S\t{id}\t{seq}\n"));
+    }
+    for (i, path) in paths.iter().enumerate() {
+        let steps: Vec<_> = path.iter().map(|gp| format!("{}{}", gp.id, gp.sign)).collect();
+        gfa.push_str(&format!("P\t{i}\t{}\t*\n", steps.join(",")));
+    }
+    fs::write(filename, gfa).unwrap();
+}
+
+#[test]
+fn reverse_steps_are_sampled_like_pfg() {
+    // the text is AT, both suffixes start on the only segment, in opposite orientations
+    let dir = TempDir::new("reverse_steps");
+    let filename = dir.join("graph.gfa");
+    let step = |sign| GraphPos { id: 0, sign, pos: 0 };
+    write_graph(&filename, &["A".to_string()], &[vec![step(Direction::Forward), step(Direction::RevComp)]]);
+    assert_eq!(Graph::from_file(&filename).text(), b"AT");
+    assert_same_sampled_arrays(&filename, Path::new("data/pftag/triggers.txt"));
+}
+
+fn graph() -> impl Strategy<Value = (Vec<String>, Vec<Vec<GraphPos>>)> {
+    prop::collection::vec("[ACGT]{1,6}", 1..6).prop_flat_map(|segments| {
+        let n = segments.len();
+        let step = (0..n, any::<bool>()).prop_map(|(id, reverse)| GraphPos {
+            id, sign: if reverse { Direction::RevComp } else { Direction::Forward }, pos: 0
+        });
+        let paths = prop::collection::vec(prop::collection::vec(step, 1..8), 1..4);
+        (Just(segments), paths)
+    })
+}
+
+proptest! {
+    #[test]
+    fn naive_sampled_arrays_are_equal_to_pfg_on_random_graph((segments, paths) in graph()) {
+        let dir = TempDir::new("random_graph");
+        let filename = dir.join("graph.gfa");
+        write_graph(&filename, &segments, &paths);
+        assert_same_sampled_arrays(&filename, &PathBuf::from("data/pftag/triggers.txt"));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::grammar::Grammar;
+use crate::repair::repair;
+use crate::graph::Graph;
+use crate::tests::TempDir;
+use proptest::prelude::*;
+
+fn assert_generates(s: &[u8]) {
+    let g = Grammar::from_text(s);
+    assert_eq!(g.len(), s.len());
+    for i in 0..g.len() {
+        assert_eq!(g[i], s[i]);
+    }
+}
+
+#[test]
+fn repair_generates_text() {
+    assert_generates(b"TGACGGGCAGT");
+    assert_generates(b"AAAAAAAAAAA");
+    assert_generates(b"GTACGTGTACGTGTGTAGTACGTCTGTGTTACGT");
+}
+
+#[test]
+fn short_texts_have_grammars() {
+    assert!(repair(b"A").is_empty());
+    assert_generates(b"");
+    assert_generates(b"A");
+    assert_generates(b"AC");
+
+    let dir = TempDir::new("short_text");
+    let slp = dir.join("short.slp");
+    for s in [&b""[..], b"A"] {
+        let g = Grammar::from_text(s).balance();
+        assert_eq!((g.len(), g.height()), (s.len(), 0));
+        g.write_binary(&slp);
+        let g = Grammar::from_file(&slp);
+        assert_eq!(g.extract(0, 10), s);
+        assert_eq!(g.lce(0, s.len()), 0);
+    }
+}
+
+#[test]
+fn repair_generates_graph_text() {
+    let text = Graph::from_file("data/real/SARS-CoV2.5.gfa").text();
+    let rules = repair(&text);
+    assert!(rules.len() < text.len() / 2);
+    assert_generates(&text);
+}
+
+proptest! {
+    #[test]
+    fn repair_generates_random_text(s in "[ACGT]*") {
+        assert_generates(s.as_bytes());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::gp::GraphPos;
+use crate::stats::{grammar_stats, tag_stats};
+use crate::tag::{Run, TagWriter};
+use crate::tests::TempDir;
+use std::path::Path;
+
+#[test]
+fn grammar_stats_are_reported() {
+    let mut output = Vec::new();
+    grammar_stats(
+        Path::new("data/pftag/test_join.txt.plainslp"), Some(Path::new("data/pftag/test.no_overlap.gfa")), &mut output
+    );
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains("rules\t18\n"));
+    assert!(output.contains("terminals\t5 (\\nACGT)\n"));
+    assert!(output.contains("length\t35\n"));
+    assert!(output.contains("GFA sequence\t13\n"));
+    assert!(output.contains("rule size [32, 64)\t1\n"));
+}
+
+#[test]
+fn tag_stats_are_reported() {
+    let dir = TempDir::new("stats");
+    let filename = dir.join("graph.tag");
+    let gp = |s: &str| s.parse::<GraphPos>().unwrap();
+    let mut writer = TagWriter::binary(&filename, 12, 0, 3, 4);
+    writer.push(Run{start: 0, end: 6, tag: gp("1+:0"), len: 5});
+    writer.push(Run{start: 2, end: 2, tag: gp("3-:4"), len: 1});
+    writer.push(Run{start: 9, end: 4, tag: gp("1+:2"), len: 4});
+    writer.push(Run{start: 5, end: 1, tag: gp("1+:0"), len: 2});
+    writer.finish();
+
+    let mut output = Vec::new();
+    tag_stats(&filename, &mut output);
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains("length\t12\n"));
+    assert!(output.contains("runs\t4\n"));
+    assert!(output.contains("length/runs\t3.00\n"));
+    assert!(output.contains("distinct tags\t3\n"));
+    assert!(output.contains("nodes\t2\n"));
+    assert!(output.contains("run length [4, 8)\t2\n"));
+    assert!(output.contains("runs per node [2, 4)\t1\n"));
+    assert!(output.contains("node 1\t3 runs\nnode 3\t1 runs\n"));
+}
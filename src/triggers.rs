@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+/// Sizes of the prefix-free parse of the text induced by a set of triggers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseStats {
+    /// number of phrases in the parse
+    pub phrases: usize,
+    /// number of distinct phrases
+    pub dictionary_phrases: usize,
+    /// total length of distinct phrases
+    pub dictionary_len: usize,
+}
+
+/// Selects triggers of a common length, so that the text is split into phrases of `phrase_len` characters on average.
+/// The most frequent k-mers are taken first, skipping k-mers occurring more often than the target number of phrases.
+/// If no k-mer fits, e.g. in texts shorter than `phrase_len`, the rarest k-mer is selected.
+/// k is shortened to the length of the text, an error is returned if the text contains no k-mer over ACGT.
+pub fn select(text: &[u8], phrase_len: usize) -> Result<Vec<Vec<u8>>, String> {
+    let k = trigger_len(phrase_len).min(text.len());
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for kmer in text.windows(k.max(1)).filter(|kmer| kmer.iter().all(|c| b"ACGT".contains(c))) {
+        *counts.entry(kmer).or_insert(0) += 1;
+    }
+    let mut kmers: Vec<_> = counts.into_iter().collect();
+    kmers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let Some(&(rarest, _)) = kmers.last() else {
+        return Err(format!("Cannot select triggers, the paths of length {} contain no {k}-mer of ACGT.", text.len()));
+    };
+
+    let target = text.len() / phrase_len.max(1);
+    let mut occurrences = 0;
+    let mut triggers = Vec::new();
+    for &(kmer, count) in &kmers {
+        if occurrences + count > target { continue; }
+        occurrences += count;
+        triggers.push(kmer.to_vec());
+    }
+    if triggers.is_empty() { triggers.push(rarest.to_vec()); }
+    return Ok(triggers);
+}
+
+/// Length of triggers, such that a single k-mer of random text occurs
+/// at least 4 times less often than once per phrase.
+fn trigger_len(phrase_len: usize) -> usize {
+    let mut k = 0;
+    while 4usize.saturating_pow(k) < phrase_len { k += 1; }
+    return k as usize + 1;
+}
+
+/// Returns sizes of the parse of the text into phrases starting and ending at trigger occurrences.
+pub fn parse_stats(text: &[u8], triggers: &[Vec<u8>]) -> ParseStats {
+    let set: HashSet<&[u8]> = triggers.iter().map(|trigger| &trigger[..]).collect();
+    let mut lengths: Vec<usize> = triggers.iter().map(|trigger| trigger.len()).filter(|&l| l > 0).collect();
+    lengths.sort_unstable();
+    lengths.dedup();
+
+    let mut dictionary = HashSet::new();
+    let mut phrases = 0;
+    let mut start = 0;
+    for i in 0..text.len() {
+        if let Some(&len) = lengths.iter().find(|&&len| i + len <= text.len() && set.contains(&text[i..i+len])) {
+            // consecutive phrases overlap by the trigger
+            if i > start {
+                dictionary.insert(&text[start..i+len]);
+                phrases += 1;
+                start = i;
+            }
+        }
+    }
+    dictionary.insert(&text[start..]);
+    phrases += 1;
+
+    let dictionary_len = dictionary.iter().map(|phrase| phrase.len()).sum();
+    return ParseStats { phrases, dictionary_phrases: dictionary.len(), dictionary_len };
+}
+
+/// Parses a trigger file with one trigger per line, empty lines are skipped.
+pub fn parse(content: &[u8]) -> Vec<Vec<u8>> {
+    return content.split(|&c| c == b'\n').filter(|line| !line.is_empty()).map(|line| line.to_vec()).collect();
+}
+
+/// Returns the content of a trigger file.
+pub fn serialize(triggers: &[Vec<u8>]) -> Vec<u8> {
+    return triggers.iter().flat_map(|trigger| trigger.iter().copied().chain([b'\n'])).collect();
+}
+
+#[test]
+fn short_texts_have_triggers() {
+    // 4-mers are selected for phrases of length 64, but there is no room for a phrase
+    assert_eq!(select(b"ACG", 64), Ok(vec![b"ACG".to_vec()]));
+    assert_eq!(select(b"ACGTACGTACGTAAGG", 64), Ok(vec![b"TAAG".to_vec()]));
+    assert!(select(b"", 64).is_err());
+    assert!(select(b"NNNN", 64).is_err());
+}
+
+#[test]
+fn parse_is_split_at_triggers() {
+    let stats = parse_stats(b"ACTAAGGTAACCTAAGG", &[b"TAA".to_vec()]);
+    // AC|TAAGG|TAACC|TAAGG: ACTAA, TAAGGTAA, TAACCTAA, TAAGG
+    assert_eq!(stats, ParseStats { phrases: 4, dictionary_phrases: 4, dictionary_len: 5 + 8 + 8 + 5 });
+}